@@ -0,0 +1,37 @@
+//! Compares the latency of fetching a page with a single candidate and a page with several candidates.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::{png_bytes, serve, Response};
+use criterion::{criterion_group, criterion_main, Criterion};
+use favilib::{Client, Favicon, FetchOptions, Url};
+
+fn fetch_benchmark(c: &mut Criterion) {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/single" => Response::ok("<head></head>"),
+        "/multiple" => Response::ok(
+            r#"<head>
+                <link rel="icon" href="/icon-16.png">
+                <link rel="icon" href="/icon-32.png">
+                <link rel="apple-touch-icon" href="/icon-180.png">
+            </head>"#,
+        ),
+        "/favicon.ico" | "/icon-16.png" => Response::ok(png_bytes(16, 16)),
+        "/icon-32.png" => Response::ok(png_bytes(32, 32)),
+        "/icon-180.png" => Response::ok(png_bytes(180, 180)),
+        _ => Response::not_found(),
+    });
+    let client = Client::new();
+    let options = FetchOptions::default();
+
+    for page in ["single", "multiple"] {
+        let url = Url::parse(&format!("{}/{}", base_url, page)).unwrap();
+        c.bench_function(&format!("fetch_{}_candidate", page), |b| {
+            b.iter(|| Favicon::fetch_with_client(url.clone(), &client, &options).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, fetch_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use super::errors::FavilibError;
+use super::http::{HttpClient, HttpResponse};
+use super::options::FetchOptions;
+use super::Favicon;
+
+/// Options for fetching the favicons of many websites.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Maximum number of websites fetched in parallel. Defaults to 8.
+    pub concurrency: usize,
+
+    /// Maximum number of requests per second sent to a single host. Has to be positive and finite.
+    /// Workers block until the host has capacity again, other hosts are fetched in the meantime.
+    /// `None` does not limit the requests. Defaults to `None`.
+    pub requests_per_host_per_second: Option<f64>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            requests_per_host_per_second: None,
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Checks that the rate limit is positive and finite.
+    pub fn validate(&self) -> Result<(), FavilibError> {
+        match self.requests_per_host_per_second {
+            Some(rate) if !(rate.is_finite() && rate > 0.0) => {
+                Err(FavilibError::RateLimitError(rate))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Fetches the favicons of many websites in parallel.
+/// Results are returned in the same order as `urls`.
+pub fn fetch_many(
+    urls: &[Url],
+    client: &dyn HttpClient,
+    options: &FetchOptions,
+    batch_options: &BatchOptions,
+) -> Vec<Result<Favicon, FavilibError>> {
+    fetch_many_with_progress(urls, client, options, batch_options, |_, _, _, _| {})
+}
+
+/// Fetches the favicons of many websites in parallel and reports each finished website.
+/// `on_progress` receives the index of the URL in `urls`, the total number of URLs, the URL and its result.
+/// It is called from the worker threads as soon as a website is done.
+/// Results are returned in the same order as `urls`. If `batch_options` are invalid, nothing is fetched and every
+/// result is the validation error.
+pub fn fetch_many_with_progress<F>(
+    urls: &[Url],
+    client: &dyn HttpClient,
+    options: &FetchOptions,
+    batch_options: &BatchOptions,
+    on_progress: F,
+) -> Vec<Result<Favicon, FavilibError>>
+where
+    F: Fn(usize, usize, &Url, &Result<Favicon, FavilibError>) + Sync,
+{
+    if let Err(FavilibError::RateLimitError(rate)) = batch_options.validate() {
+        return urls
+            .iter()
+            .map(|_| Err(FavilibError::RateLimitError(rate)))
+            .collect();
+    }
+
+    let total = urls.len();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..total).map(|_| None).collect::<Vec<_>>());
+    let workers = batch_options.concurrency.clamp(1, total.max(1));
+    let limited_client;
+    let client = match batch_options.requests_per_host_per_second {
+        Some(rate) => {
+            limited_client = RateLimitedClient {
+                inner: client,
+                limiter: RateLimiter::new(rate),
+            };
+            &limited_client as &dyn HttpClient
+        }
+        None => client,
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(url) = urls.get(index) else {
+                    break;
+                };
+
+                let result = Favicon::fetch_with_client(url.clone(), client, options);
+                on_progress(index, total, url, &result);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every URL is fetched by a worker"))
+        .collect()
+}
+
+/// Token bucket rate limiter keyed by host.
+/// Every host starts with a full bucket, so a single request is never delayed.
+struct RateLimiter {
+    rate: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.max(f64::MIN_POSITIVE),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Blocks until a request to the given host is allowed.
+    fn acquire(&self, host: &str) {
+        while let Some(wait) = self.try_acquire(host) {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Takes a token of the host if one is available, or else returns how long to wait before trying again.
+    /// Waits are capped, so even very low rates only block for `MAX_WAIT` before the bucket is checked again.
+    fn try_acquire(&self, host: &str) -> Option<Duration> {
+        const MAX_WAIT: Duration = Duration::from_secs(1);
+
+        let capacity = self.rate.max(1.0);
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let bucket = buckets.entry(host.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return None;
+        }
+        let wait =
+            Duration::try_from_secs_f64((1.0 - bucket.tokens) / self.rate).unwrap_or(MAX_WAIT);
+        Some(wait.min(MAX_WAIT))
+    }
+}
+
+/// Client which passes every request through a `RateLimiter` first.
+struct RateLimitedClient<'a> {
+    inner: &'a dyn HttpClient,
+    limiter: RateLimiter,
+}
+
+impl HttpClient for RateLimitedClient<'_> {
+    fn get(&self, url: &Url, headers: &[(&str, &str)]) -> Result<HttpResponse, FavilibError> {
+        self.limiter.acquire(url.host_str().unwrap_or_default());
+        self.inner.get(url, headers)
+    }
+
+    fn head(&self, url: &Url, headers: &[(&str, &str)]) -> Result<HttpResponse, FavilibError> {
+        self.limiter.acquire(url.host_str().unwrap_or_default());
+        self.inner.head(url, headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{png_bytes, MockClient};
+
+    #[test]
+    fn test_fetch_many_reports_progress() -> anyhow::Result<()> {
+        let client = MockClient::default()
+            .with("https://a.example.com/", "<head></head>")
+            .with("https://a.example.com/favicon.ico", png_bytes(16, 16));
+        let urls = vec![
+            Url::parse("https://a.example.com")?,
+            Url::parse("https://b.example.com")?,
+        ];
+        let reported = Mutex::new(vec![]);
+
+        let results = fetch_many_with_progress(
+            &urls,
+            &client,
+            &FetchOptions::default(),
+            &BatchOptions::default(),
+            |index, total, url, result| {
+                reported
+                    .lock()
+                    .unwrap()
+                    .push((index, total, url.clone(), result.is_ok()));
+            },
+        );
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+
+        let mut reported = reported.into_inner().unwrap();
+        reported.sort_by_key(|(index, ..)| *index);
+        assert_eq!(
+            reported,
+            vec![
+                (0, 2, urls[0].clone(), true),
+                (1, 2, urls[1].clone(), false)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limiter_delays_requests_per_host() {
+        let limiter = RateLimiter::new(20.0);
+        limiter.buckets.lock().unwrap().insert(
+            "example.com".to_string(),
+            Bucket {
+                tokens: 0.0,
+                updated: Instant::now(),
+            },
+        );
+
+        assert_eq!(limiter.try_acquire("other.example.com"), None);
+
+        let wait = limiter.try_acquire("example.com").unwrap();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_rate_limiter_caps_waits() {
+        let limiter = RateLimiter::new(1e-300);
+
+        assert_eq!(limiter.try_acquire("example.com"), None);
+        assert_eq!(
+            limiter.try_acquire("example.com"),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_client_forwards_head_requests() -> anyhow::Result<()> {
+        #[derive(Default)]
+        struct MethodRecorder(Mutex<Vec<&'static str>>);
+
+        impl MethodRecorder {
+            fn record(
+                &self,
+                method: &'static str,
+                url: &Url,
+            ) -> Result<HttpResponse, FavilibError> {
+                self.0.lock().unwrap().push(method);
+                Ok(HttpResponse {
+                    url: url.clone(),
+                    status: 200,
+                    headers: vec![],
+                    body: vec![],
+                })
+            }
+        }
+
+        impl HttpClient for MethodRecorder {
+            fn get(&self, url: &Url, _: &[(&str, &str)]) -> Result<HttpResponse, FavilibError> {
+                self.record("GET", url)
+            }
+
+            fn head(&self, url: &Url, _: &[(&str, &str)]) -> Result<HttpResponse, FavilibError> {
+                self.record("HEAD", url)
+            }
+        }
+
+        let inner = MethodRecorder::default();
+        let client = RateLimitedClient {
+            inner: &inner,
+            limiter: RateLimiter::new(1000.0),
+        };
+        let url = Url::parse("https://example.com/favicon.ico")?;
+        client.head(&url, &[])?;
+        client.get(&url, &[])?;
+
+        assert_eq!(*inner.0.lock().unwrap(), ["HEAD", "GET"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_rates_are_rejected() {
+        let client = MockClient::default();
+        let urls = vec![Url::parse("https://example.com").unwrap()];
+
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let batch_options = BatchOptions {
+                requests_per_host_per_second: Some(rate),
+                ..Default::default()
+            };
+            assert!(batch_options.validate().is_err(), "{}", rate);
+
+            let results = fetch_many(&urls, &client, &FetchOptions::default(), &batch_options);
+            assert!(matches!(
+                results[..],
+                [Err(FavilibError::RateLimitError(_))]
+            ));
+        }
+    }
+}
@@ -1,11 +1,15 @@
-use clap::{Parser, Subcommand, ValueEnum};
-use image::ImageFormat;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use image::{GenericImageView as _, ImageFormat};
+use std::collections::HashSet;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use thiserror::Error;
 use url::Url;
 
 use favilib::errors::FavilibError;
+use favilib::BatchOptions;
 use favilib::Favicon;
+use favilib::FetchOptions;
 use favilib::ImageSize;
 
 #[derive(Parser, Debug, Clone)]
@@ -18,48 +22,169 @@ struct Cli {
 #[derive(Subcommand, Debug, Clone)]
 enum Commands {
     /// Fetches favicons from websites.
-    Fetch {
-        /// URL of the website. If schema is omitted, https will be used.
-        url: String,
+    Fetch(FetchArgs),
 
-        #[arg(short, long, default_value = "default")]
-        size: Option<ImageSize>,
+    /// Resizes and converts local favicon files.
+    Convert(ConvertArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+struct FetchArgs {
+    /// URL of the website. If schema is omitted, https will be used.
+    #[arg(required_unless_present = "batch")]
+    url: Option<String>,
+
+    #[command(flatten)]
+    transform: TransformArgs,
+
+    /// Path to save the favicon. In batch mode the directory the favicons are saved to
+    #[arg(short, long, required_unless_present = "stdout")]
+    path: Option<String>,
+
+    /// Set this flag to only print the URL of the favicon. Candidates are checked with HEAD requests, not downloaded,
+    /// so `min-size` is not applied
+    #[arg(long)]
+    url_only: bool,
+
+    /// Set this flag to only write the favicon bytes to stdout. Mutually exclusive with `path`.
+    /// Without `format` the fetched bytes are written unchanged.
+    #[arg(long, required_unless_present = "path")]
+    stdout: bool,
+
+    /// Set this flag to print the MIME type of the written favicon to stderr
+    #[arg(long)]
+    print_format: bool,
+
+    /// Set this flag to render a preview of the written favicon to stderr. Needs a terminal with 24-bit colors
+    #[arg(long)]
+    preview: bool,
 
-        #[arg(short, long, default_value = "png")]
-        format: Option<InternalImageFormat>,
+    /// Writes a snippet embedding the favicon as a data URI instead of the image
+    #[arg(long, value_enum, conflicts_with = "url_only")]
+    emit: Option<Snippet>,
 
-        /// Path to save the favicon
-        #[arg(short, long, required_unless_present = "stdout")]
-        path: Option<String>,
+    /// CSS selector of the rule written by `--emit css`
+    #[arg(long, default_value = ".favicon")]
+    css_selector: String,
 
-        /// Set this flag to only print the URL of the favicon
-        #[arg(long)]
-        url_only: bool,
+    /// Set this flag to search the whole page and its web app manifest for icons instead of only the head section
+    #[arg(long)]
+    whole_document: bool,
 
-        /// Set this flag to only write the favicon bytes to stdout. Mutually exclusive with `path`.
-        #[arg(long, required_unless_present = "path")]
-        stdout: bool,
-    },
+    /// Set this flag to fetch the favicon from the canonical page, e.g. for AMP pages
+    #[arg(long)]
+    follow_canonical: bool,
+
+    /// Set this flag to accept invalid TLS certificates, e.g. self-signed or expired ones.
+    /// Insecure: the connection is no longer protected against man-in-the-middle attacks
+    #[arg(long)]
+    insecure: bool,
+
+    /// Set this flag to retry over plain http if the website can't be reached over https.
+    /// Insecure: the response is not protected against tampering
+    #[arg(long)]
+    allow_http: bool,
+
+    /// Set this flag to not retry the URL with `www.` added or stripped if it yields no favicon
+    #[arg(long)]
+    no_www_fallback: bool,
+
+    /// Host which is not retried with `www.` added or stripped. Can be given multiple times
+    #[arg(long = "no-www-fallback-host", value_name = "HOST")]
+    no_www_fallback_hosts: Vec<String>,
+
+    /// Minimum dimensions of the favicon, e.g. `32x32`. Smaller candidates are skipped.
+    /// If no candidate is large enough, a smaller favicon is used with a warning unless `strict` is set.
+    /// In batch mode websites without a large enough favicon always fail.
+    /// Not applied with `url-only`, since the candidates are not downloaded.
+    #[arg(long, value_parser = parse_dimensions)]
+    min_size: Option<(u32, u32)>,
+
+    /// Set this flag to fail instead of falling back if no favicon meets `min-size`
+    #[arg(long, requires = "min_size")]
+    strict: bool,
+
+    /// Set this flag to print the candidates which were rejected to stderr
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// File with one URL per line to fetch the favicons for. Empty lines and lines starting with `#` are skipped.
+    /// Favicons are saved to the directory given by `path` as `<host>.<extension>`.
+    /// Exits with status 1 if any favicon could not be fetched or saved.
+    #[arg(long, conflicts_with_all = ["url", "stdout", "url_only"], requires = "path")]
+    batch: Option<String>,
+
+    /// Naming scheme of the files saved in batch mode. Names which are already taken get a `-<n>` suffix.
+    #[arg(long, value_enum, default_value = "domain")]
+    naming: BatchNaming,
+
+    /// Number of favicons fetched in parallel in batch mode. Has to be at least 1.
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    concurrency: u32,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ConvertArgs {
+    /// Path of the local favicon file.
+    input: String,
+
+    #[command(flatten)]
+    transform: TransformArgs,
+
+    /// Path to save the favicon
+    #[arg(short, long, required_unless_present = "stdout")]
+    path: Option<String>,
+
+    /// Set this flag to only write the favicon bytes to stdout. Mutually exclusive with `path`.
+    /// Without `format` the input bytes are written unchanged.
+    #[arg(long, required_unless_present = "path")]
+    stdout: bool,
+
+    /// Set this flag to print the MIME type of the written favicon to stderr
+    #[arg(long)]
+    print_format: bool,
+
+    /// Set this flag to render a preview of the written favicon to stderr. Needs a terminal with 24-bit colors
+    #[arg(long)]
+    preview: bool,
+}
+
+/// Size and format options shared by all commands.
+#[derive(Args, Debug, Clone)]
+struct TransformArgs {
+    #[arg(short, long, default_value = "default")]
+    size: Option<ImageSize>,
+
+    /// Width of the favicon. If no height is given the aspect ratio is preserved.
+    /// Mutually exclusive with `size`.
+    #[arg(long, conflicts_with = "size")]
+    width: Option<u32>,
+
+    /// Height of the favicon. If no width is given the aspect ratio is preserved.
+    /// Mutually exclusive with `size`.
+    #[arg(long, conflicts_with = "size")]
+    height: Option<u32>,
+
+    /// Format of the favicon. If omitted, the format of the source favicon is kept.
+    /// `auto` picks PNG for transparent and flat icons and JPEG for opaque photographic ones.
+    #[arg(short, long)]
+    format: Option<InternalImageFormat>,
 }
 
 fn main() -> Result<(), ExternalError> {
     let args = Cli::parse();
 
     match args.command {
-        Some(Commands::Fetch {
-            url,
-            size,
-            format,
-            path,
-            url_only,
-            stdout,
-        }) => match handle_fetch(url, size, format, path, url_only, stdout) {
-            Ok(_) => {}
-            Err(e) => {
+        Some(command) => {
+            let result = match command {
+                Commands::Fetch(args) => handle_fetch(args),
+                Commands::Convert(args) => handle_convert(args),
+            };
+            if let Err(e) = result {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
-        },
+        }
         None => {
             eprintln!("No command provided. Use --help to see available commands.");
         }
@@ -68,39 +193,297 @@ fn main() -> Result<(), ExternalError> {
     Ok(())
 }
 
-fn handle_fetch(
-    url: String,
-    size: Option<ImageSize>,
-    format: Option<InternalImageFormat>,
-    path: Option<String>,
-    url_only: bool,
-    stdout: bool,
-) -> Result<(), ExternalError> {
-    let url = parse_url(&url)?;
-
-    let size = size.unwrap_or(ImageSize::Default);
-    let format: image::ImageFormat = format.unwrap_or(InternalImageFormat::Png).into();
-
-    let favicon = Favicon::fetch(url, None)?;
-    let favicon = favicon.resize(size);
+fn handle_fetch(args: FetchArgs) -> Result<(), ExternalError> {
+    let url = match (&args.url, &args.batch) {
+        (_, Some(list)) => return handle_batch(&args, list),
+        (Some(url), None) => favilib::parse_website_url(url)?,
+        (None, None) => return Err(ExternalError::InvalidUrlError),
+    };
 
-    let path = path.clone().unwrap_or_default();
+    let path = args.path.clone().unwrap_or_default();
 
     let path = Path::new(&path);
 
-    let target = if stdout {
+    let target = if args.stdout {
         ExportTarget::Stdout
     } else {
         ExportTarget::File(Path::new(path))
     };
 
-    match url_only {
-        true => write_url(favicon.url().clone(), target)?,
-        false => write_favicon(favicon, target, format)?,
+    if args.url_only {
+        let options = fetch_options(&args);
+        let client = favilib::default_client(&options).map_err(|_| ExternalError::FetchError)?;
+        let favicon_url = Favicon::resolve_url(url, &client, &options)?;
+        return Ok(write_text(favicon_url.as_str(), target)?);
+    }
+
+    let favicon = fetch_favicon(url.clone(), &args)?;
+    if let Some(page_url) = favicon
+        .page_url()
+        .filter(|page| page.scheme() != url.scheme())
+    {
+        eprintln!("Warning: fetched over {}: {}", page_url.scheme(), page_url);
+    }
+    let (favicon, format) = transform(favicon, &args.transform)?;
+
+    if args.print_format {
+        eprintln!("{}", format.to_mime_type());
+    }
+    if args.preview {
+        eprint!("{}", favicon.to_ansi_preview(preview_columns()));
+    }
+
+    match args.emit {
+        Some(Snippet::Html) => write_text(&favicon.to_html_link(format)?, target)?,
+        Some(Snippet::Css) => write_text(
+            &favicon.to_css_background(&args.css_selector, format)?,
+            target,
+        )?,
+        None => write_favicon(favicon, target, format)?,
     };
     Ok(())
 }
 
+/// Fetches the favicon with the options given on the command line.
+/// Without `strict` the favicon is fetched again without the minimum size if no candidate meets it.
+fn fetch_favicon(url: Url, args: &FetchArgs) -> Result<Favicon, ExternalError> {
+    let options = fetch_options(args);
+    let error = match Favicon::fetch_with_options(url.clone(), None, &options) {
+        Ok(favicon) => return Ok(favicon),
+        Err(e) => e,
+    };
+
+    if args.verbose {
+        if let FavilibError::AllCandidatesFailedError { attempts } = &error {
+            for (candidate, reason) in attempts {
+                eprintln!("Rejected {}: {}", candidate, reason);
+            }
+        }
+    }
+    let Some((width, height)) = args.min_size.filter(|_| !args.strict) else {
+        return Err(error.into());
+    };
+
+    let options = FetchOptions {
+        min_dimensions: None,
+        ..options
+    };
+    let favicon = Favicon::fetch_with_options(url, None, &options)?;
+    let (found_width, found_height) = favicon.image().dimensions();
+    eprintln!(
+        "Warning: no favicon of at least {}x{} found, using {}x{}",
+        width, height, found_width, found_height
+    );
+    Ok(favicon)
+}
+
+fn handle_convert(args: ConvertArgs) -> Result<(), ExternalError> {
+    let favicon = Favicon::from_path(&args.input).map_err(|e| match e {
+        FavilibError::IoError(_) => ExternalError::ReadError,
+        e => e.into(),
+    })?;
+    let (favicon, format) = transform(favicon, &args.transform)?;
+
+    if args.print_format {
+        eprintln!("{}", format.to_mime_type());
+    }
+    if args.preview {
+        eprint!("{}", favicon.to_ansi_preview(preview_columns()));
+    }
+
+    let path = args.path.unwrap_or_default();
+    let target = if args.stdout {
+        ExportTarget::Stdout
+    } else {
+        ExportTarget::File(Path::new(&path))
+    };
+
+    Ok(write_favicon(favicon, target, format)?)
+}
+
+/// Fetches the favicons of all URLs in the given list and saves them to the directory given by `path`.
+/// Failed websites are reported on stderr without aborting the remaining ones.
+fn handle_batch(args: &FetchArgs, list: &str) -> Result<(), ExternalError> {
+    let list = std::fs::read_to_string(list).map_err(|_| ExternalError::ReadError)?;
+    let urls = parse_url_list(&list)?;
+
+    let directory = Path::new(args.path.as_deref().unwrap_or_default());
+    std::fs::create_dir_all(directory).map_err(|_| ExternalError::WriteError)?;
+
+    let batch_options = BatchOptions {
+        concurrency: args.concurrency as usize,
+        ..Default::default()
+    };
+    let options = fetch_options(args);
+    let client = favilib::default_client(&options).map_err(|_| ExternalError::FetchError)?;
+    let finished = AtomicUsize::new(0);
+    let results = favilib::fetch_many_with_progress(
+        &urls,
+        &client,
+        &options,
+        &batch_options,
+        |_, total, url, result| {
+            let finished = finished.fetch_add(1, Ordering::Relaxed) + 1;
+            match result {
+                Ok(favicon) => eprintln!("[{}/{}] {}: {}", finished, total, url, favicon.url()),
+                Err(e) => eprintln!("[{}/{}] {}: {}", finished, total, url, e),
+            }
+        },
+    );
+
+    let mut failed = 0;
+    let mut used_names = HashSet::new();
+    for (url, result) in urls.iter().zip(results) {
+        let saved = result
+            .map_err(ExternalError::from)
+            .and_then(|favicon| transform(favicon, &args.transform))
+            .and_then(|(favicon, format)| {
+                let stem = batch_file_stem(url, favicon.url(), &args.naming);
+                let name = unique_file_name(&mut used_names, &stem, format);
+                let path = directory.join(name);
+                Ok(favicon.export(path, format)?)
+            });
+        if let Err(e) = saved {
+            eprintln!("Error: {}: {}", url, e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(ExternalError::BatchError(failed, urls.len()));
+    }
+    Ok(())
+}
+
+/// Parses a list with one URL per line. Empty lines and lines starting with `#` are skipped.
+fn parse_url_list(list: &str) -> Result<Vec<Url>, FavilibError> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(favilib::parse_website_url)
+        .collect()
+}
+
+/// Naming schemes of the files saved in batch mode.
+#[derive(Debug, Clone, ValueEnum)]
+enum BatchNaming {
+    /// Host of the website, e.g. `github.com.png`
+    Domain,
+    /// 64-bit FNV-1a hash of the full website URL in hexadecimal, e.g. `5e1c309dae7f45e0.png`.
+    /// The hash is fixed, so names stay the same across builds
+    Hash,
+    /// File name of the favicon URL, e.g. `apple-touch-icon.png`
+    Filename,
+}
+
+/// 64-bit FNV-1a hash. Unlike the hasher of the standard library its output never changes between Rust releases.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Name of the file a favicon fetched in batch mode is saved to, without extension.
+/// Characters other than ASCII alphanumerics, `-`, `_` and `.` are replaced with `_`.
+fn batch_file_stem(url: &Url, favicon_url: &Url, naming: &BatchNaming) -> String {
+    let stem = match naming {
+        BatchNaming::Domain => url.host_str().unwrap_or("favicon").to_string(),
+        BatchNaming::Hash => format!("{:016x}", fnv1a_64(url.as_str().as_bytes())),
+        BatchNaming::Filename => {
+            let name = favicon_url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .unwrap_or_default();
+            let stem = Path::new(name).file_stem().and_then(|stem| stem.to_str());
+            stem.filter(|stem| !stem.is_empty())
+                .unwrap_or("favicon")
+                .to_string()
+        }
+    };
+
+    stem.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Appends the extension of the format and a `-<n>` suffix if the name was already used.
+fn unique_file_name(used: &mut HashSet<String>, stem: &str, format: ImageFormat) -> String {
+    let extension = format.extensions_str().first().unwrap_or(&"img");
+    let mut name = format!("{}.{}", stem, extension);
+    let mut suffix = 1;
+    while !used.insert(name.clone()) {
+        name = format!("{}-{}.{}", stem, suffix, extension);
+        suffix += 1;
+    }
+    name
+}
+
+fn fetch_options(args: &FetchArgs) -> FetchOptions {
+    FetchOptions {
+        scan_whole_document: args.whole_document,
+        follow_canonical: args.follow_canonical,
+        accept_invalid_certs: args.insecure,
+        allow_http_fallback: args.allow_http,
+        disable_www_fallback: args.no_www_fallback,
+        www_fallback_excluded_hosts: args.no_www_fallback_hosts.clone(),
+        min_dimensions: args.min_size,
+        ..Default::default()
+    }
+}
+
+/// Applies the size and format given on the command line.
+/// Returns the resized favicon and the format it should be written in.
+fn transform(
+    favicon: Favicon,
+    args: &TransformArgs,
+) -> Result<(Favicon, ImageFormat), ExternalError> {
+    let size = match (args.width, args.height) {
+        (None, None) => args.size.clone().unwrap_or(ImageSize::Default),
+        (width, height) => size_from_dimensions(width, height, favicon.image().dimensions()),
+    };
+    let source_format = favicon.format_of_bytes();
+    let favicon = match size {
+        ImageSize::Default => favicon,
+        size => favicon.try_resize(size)?,
+    };
+
+    let format = match &args.format {
+        Some(format) => format.image_format(&favicon),
+        None => source_format.unwrap_or(ImageFormat::Png),
+    };
+
+    Ok((favicon, format))
+}
+
+/// Builds a custom size from the given dimensions.
+/// A missing dimension is derived from the original dimensions to preserve the aspect ratio.
+fn size_from_dimensions(
+    width: Option<u32>,
+    height: Option<u32>,
+    (original_width, original_height): (u32, u32),
+) -> ImageSize {
+    let scale = |value: u32, from: u32, to: u32| {
+        ((value as f64 * to as f64 / from.max(1) as f64).round() as u32).max(1)
+    };
+
+    match (width, height) {
+        (Some(width), Some(height)) => ImageSize::Custom(width, height),
+        (Some(width), None) => {
+            ImageSize::Custom(width, scale(width, original_width, original_height))
+        }
+        (None, Some(height)) => {
+            ImageSize::Custom(scale(height, original_height, original_width), height)
+        }
+        (None, None) => ImageSize::Default,
+    }
+}
+
 enum ExportTarget<'a> {
     File(&'a Path),
     Stdout,
@@ -112,59 +495,93 @@ fn write_favicon(
     format: ImageFormat,
 ) -> Result<(), FavilibError> {
     match target {
-        ExportTarget::File(path) => favicon.export(path, format),
+        ExportTarget::File(path) => {
+            if ImageFormat::from_path(path).is_ok_and(|extension| extension != format) {
+                eprintln!("Warning: writing {:?} image to {}", format, path.display());
+            }
+            favicon.export(path, format)
+        }
         ExportTarget::Stdout => favicon.write_to_stdout(format),
     }
 }
 
-fn write_url(url: Url, target: ExportTarget) -> Result<(), FavilibError> {
+fn write_text(text: &str, target: ExportTarget) -> Result<(), FavilibError> {
     match target {
         ExportTarget::File(path) => {
-            std::fs::write(path, url.as_str())?;
+            std::fs::write(path, text)?;
         }
         ExportTarget::Stdout => {
-            println!("{}", url);
+            println!("{}", text);
         }
     };
     Ok(())
 }
 
-/// Parses a URL string into a `Url` struct.
-/// If scheme is missing adds https as scheme.
-fn parse_url(url: &str) -> Result<Url, FavilibError> {
-    let url = if url.starts_with("http://") || url.starts_with("https://") {
-        url.to_string()
-    } else {
-        format!("https://{}", url)
-    };
+/// Snippets which embed the favicon as a data URI.
+#[derive(Debug, Clone, ValueEnum)]
+enum Snippet {
+    /// `<link rel="icon">` tag
+    Html,
+    /// CSS rule setting the favicon as `background-image`
+    Css,
+}
 
-    Ok(Url::parse(&url)?)
+/// Parses dimensions in the `WxH` or `W,H` form, e.g. `32x32`.
+fn parse_dimensions(value: &str) -> Result<(u32, u32), String> {
+    match ImageSize::from(value) {
+        ImageSize::Custom(width, height) => Ok((width, height)),
+        _ => Err(format!(
+            "invalid dimensions `{}`, expected e.g. 32x32",
+            value
+        )),
+    }
 }
 
+/// Width of the preview in characters: the terminal width given by `COLUMNS`, but at most 32 characters.
+fn preview_columns() -> u32 {
+    const MAX_PREVIEW_COLUMNS: u32 = 32;
+
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(MAX_PREVIEW_COLUMNS)
+        .min(MAX_PREVIEW_COLUMNS)
+}
+
+/// Parses a URL string into a `Url` struct.
+/// If scheme is missing adds https as scheme.
+
 #[derive(Debug, Clone, ValueEnum)]
 enum InternalImageFormat {
     Png,
     Jpeg,
     WebP,
     Ico,
+    Auto,
 }
 
-impl From<InternalImageFormat> for image::ImageFormat {
-    fn from(value: InternalImageFormat) -> Self {
-        match value {
-            InternalImageFormat::Png => image::ImageFormat::Png,
-            InternalImageFormat::Jpeg => image::ImageFormat::Jpeg,
-            InternalImageFormat::WebP => image::ImageFormat::WebP,
-            InternalImageFormat::Ico => image::ImageFormat::Ico,
+impl InternalImageFormat {
+    /// Resolves `Auto` to the format recommended for the favicon.
+    fn image_format(&self, favicon: &Favicon) -> ImageFormat {
+        match self {
+            InternalImageFormat::Png => ImageFormat::Png,
+            InternalImageFormat::Jpeg => ImageFormat::Jpeg,
+            InternalImageFormat::WebP => ImageFormat::WebP,
+            InternalImageFormat::Ico => ImageFormat::Ico,
+            InternalImageFormat::Auto => favicon.recommended_format(),
         }
     }
 }
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 enum ExternalError {
     #[error("Invalid Url Provided")]
     InvalidUrlError,
 
+    #[error("Invalid size provided")]
+    InvalidSizeError,
+
     #[error("Could not connect to website")]
     FetchError,
 
@@ -173,6 +590,16 @@ enum ExternalError {
 
     #[error("Could not write Favicons to file")]
     WriteError,
+
+    #[error("Could not read input file")]
+    ReadError,
+
+    #[error("{0} of {1} favicons could not be fetched")]
+    BatchError(usize, usize),
+
+    /// Errors without a more specific message, e.g. unsupported content types and undecodable images.
+    #[error("{0}")]
+    FaviconError(FavilibError),
 }
 
 impl From<FavilibError> for ExternalError {
@@ -180,8 +607,185 @@ impl From<FavilibError> for ExternalError {
         match value {
             FavilibError::UrlParseError(_) => ExternalError::InvalidUrlError,
             FavilibError::NoFaviconFoundError => ExternalError::NoFaviconFoundError,
-            FavilibError::FetchError(_) => ExternalError::FetchError,
-            _ => ExternalError::WriteError,
+            FavilibError::AllCandidatesFailedError { .. } => ExternalError::NoFaviconFoundError,
+            FavilibError::FetchError(_) | FavilibError::TimeoutError { .. } => {
+                ExternalError::FetchError
+            }
+            FavilibError::SizeParseError | FavilibError::SizeTooLargeError(_) => {
+                ExternalError::InvalidSizeError
+            }
+            FavilibError::IoError(_) => ExternalError::WriteError,
+            e => ExternalError::FaviconError(e),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_from_dimensions_preserves_aspect_ratio() {
+        assert_eq!(
+            size_from_dimensions(Some(32), None, (64, 128)),
+            ImageSize::Custom(32, 64)
+        );
+        assert_eq!(
+            size_from_dimensions(None, Some(32), (64, 128)),
+            ImageSize::Custom(16, 32)
+        );
+        assert_eq!(
+            size_from_dimensions(Some(10), Some(20), (64, 128)),
+            ImageSize::Custom(10, 20)
+        );
+    }
+
+    #[test]
+    fn test_width_conflicts_with_size() {
+        let result = Cli::try_parse_from([
+            "favilib",
+            "fetch",
+            "github.com",
+            "--stdout",
+            "--size",
+            "small",
+            "--width",
+            "32",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_concurrency_has_to_be_positive() {
+        let parse = |concurrency: &str| {
+            Cli::try_parse_from([
+                "favilib",
+                "fetch",
+                "--batch",
+                "urls.txt",
+                "--path",
+                "favicons",
+                "--concurrency",
+                concurrency,
+            ])
+        };
+        assert!(parse("0").is_err());
+        assert!(parse("4").is_ok());
+    }
+
+    #[test]
+    fn test_parse_min_size() {
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(
+                ["favilib", "fetch", "github.com", "--stdout"]
+                    .iter()
+                    .chain(args),
+            )
+        };
+
+        assert!(parse(&["--min-size", "32x48"]).is_ok());
+        assert!(parse(&["--min-size", "large"]).is_err());
+        assert!(parse(&["--strict"]).is_err());
+        assert_eq!(parse_dimensions("32,48"), Ok((32, 48)));
+    }
+
+    #[test]
+    fn test_convert_requires_output() {
+        assert!(Cli::try_parse_from(["favilib", "convert", "favicon.ico"]).is_err());
+        assert!(Cli::try_parse_from([
+            "favilib",
+            "convert",
+            "favicon.ico",
+            "--size",
+            "32x32",
+            "--format",
+            "png",
+            "--path",
+            "favicon.png",
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn test_batch_file_names_are_unique() -> Result<(), url::ParseError> {
+        let url = Url::parse("https://example.com/page")?;
+        let favicon_url = Url::parse("https://cdn.example.com/icons/apple-touch-icon.png?v=2")?;
+
+        assert_eq!(
+            batch_file_stem(&url, &favicon_url, &BatchNaming::Domain),
+            "example.com"
+        );
+        assert_eq!(
+            batch_file_stem(&url, &favicon_url, &BatchNaming::Filename),
+            "apple-touch-icon"
+        );
+        assert_eq!(
+            batch_file_stem(&url, &favicon_url, &BatchNaming::Hash),
+            format!("{:016x}", fnv1a_64(b"https://example.com/page"))
+        );
+        assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+
+        let mut used = HashSet::new();
+        assert_eq!(
+            unique_file_name(&mut used, "favicon", ImageFormat::Png),
+            "favicon.png"
+        );
+        assert_eq!(
+            unique_file_name(&mut used, "favicon", ImageFormat::Png),
+            "favicon-1.png"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_fails_if_any_favicon_fails() {
+        let name = format!("favilib-{}-batch", std::process::id());
+        let directory = std::env::temp_dir().join(&name);
+        let list = std::env::temp_dir().join(format!("{}.txt", name));
+        std::fs::write(&list, "http://127.0.0.1:1\nhttp://127.0.0.1:1/page\n").unwrap();
+
+        let cli = Cli::try_parse_from([
+            "favilib",
+            "fetch",
+            "--batch",
+            list.to_str().unwrap(),
+            "--path",
+            directory.to_str().unwrap(),
+        ])
+        .unwrap();
+        let Some(Commands::Fetch(args)) = cli.command else {
+            panic!("expected the fetch command");
+        };
+        let result = handle_fetch(args);
+
+        std::fs::remove_file(&list).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(result, Err(ExternalError::BatchError(2, 2))));
+    }
+
+    #[test]
+    fn test_unmapped_errors_keep_their_message() {
+        let error = ExternalError::from(FavilibError::ContentTypeError("application/pdf".into()));
+        assert_eq!(
+            error.to_string(),
+            "Page has unsupported content type application/pdf"
+        );
+        assert_eq!(
+            ExternalError::from(FavilibError::ImageLimitsExceededError).to_string(),
+            "Image exceeds the decoding limits"
+        );
+    }
+
+    #[test]
+    fn test_parse_url_list_skips_empty_lines_and_comments() {
+        let urls = parse_url_list("github.com\n\n# comment\n  http://example.com  \n").unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                Url::parse("https://github.com").unwrap(),
+                Url::parse("http://example.com").unwrap()
+            ]
+        );
+    }
+}
@@ -1,25 +1,89 @@
+use std::fmt;
 use thiserror::Error;
 
+/// New variants may be added in minor releases, so matches need a wildcard arm.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum FavilibError {
+    #[cfg(feature = "reqwest")]
     #[error("Failed to fetch favicon")]
     FetchError(#[from] reqwest::Error),
 
     #[error("No favicon found on website")]
     NoFaviconFoundError,
 
+    /// Every candidate URL failed. Contains the candidate URLs with the error they failed with.
+    /// Errors of the requests, e.g. `TimeoutError` with `FetchStage::Icon`, are kept, rejections are `OtherError`s.
+    #[error("No favicon found on website")]
+    AllCandidatesFailedError {
+        attempts: Vec<(url::Url, FavilibError)>,
+    },
+
     #[error("Failed to decode favicon")]
     ImageDecodeError(#[from] image::ImageError),
 
+    #[error("Image exceeds the decoding limits")]
+    ImageLimitsExceededError,
+
+    /// A request timed out. Contains the stage of the fetch and the URL of the request, which may be a redirect target.
+    #[error("Timed out fetching the {stage} at {url}")]
+    TimeoutError { stage: FetchStage, url: url::Url },
+
+    /// The page response is neither HTML nor a decodable image. Contains the content type of the response.
+    #[error("Page has unsupported content type {0}")]
+    ContentTypeError(String),
+
     #[error("Failed to parse URL")]
     UrlParseError(#[from] url::ParseError),
 
     #[error("Failed to parse size")]
     SizeParseError,
 
+    #[error("Invalid favicon spec {0}")]
+    SpecParseError(String),
+
+    #[error("Device pixel ratio must be positive, got {0}")]
+    DevicePixelRatioError(f32),
+
+    #[error("Requests per host per second must be positive and finite, got {0}")]
+    RateLimitError(f64),
+
+    #[error("Size exceeds the maximum of {0} pixels")]
+    SizeTooLargeError(u32),
+
+    #[error("No ICO entry with a size of {0}x{1}")]
+    IcoEntryNotFoundError(u32, u32),
+
+    #[error("Image has no pixels")]
+    EmptyImageError,
+
+    #[error("Image format {0:?} is not allowed")]
+    FormatNotAllowedError(image::ImageFormat),
+
+    #[error("File extension does not match image format {0:?}")]
+    FormatMismatchError(image::ImageFormat),
+
     #[error("Failed to write to file")]
     IoError(#[from] std::io::Error),
 
-    #[error("Failed to write to stdout")]
+    #[error("{0:#}")]
     OtherError(#[from] anyhow::Error),
 }
+
+/// Stage of a fetch a request belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchStage {
+    /// Request of the HTML page.
+    Page,
+    /// Request of a favicon candidate.
+    Icon,
+}
+
+impl fmt::Display for FetchStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchStage::Page => write!(f, "page"),
+            FetchStage::Icon => write!(f, "icon"),
+        }
+    }
+}
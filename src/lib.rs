@@ -1,180 +1,2445 @@
 use anyhow::{Context as _, Result};
-use image::{imageops::FilterType, io::Reader as ImageReader};
-pub use image::{DynamicImage, ImageFormat};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{
+    imageops, imageops::FilterType, AnimationDecoder as _, Frame, GenericImageView as _,
+    ImageDecoder as _, ImageError, ImageReader,
+};
+pub use image::{DynamicImage, ImageFormat, RgbImage, Rgba, RgbaImage};
+#[cfg(feature = "reqwest")]
 pub use reqwest::blocking::Client;
-use std::io::{self, Cursor, Write as _};
+use std::fmt;
+use std::io::{self, Cursor, Write};
 use std::path::Path;
+use std::sync::OnceLock;
 pub use url::Url;
 
+pub use batch::{fetch_many, fetch_many_with_progress, BatchOptions};
 use errors::FavilibError;
+pub use http::{HttpClient, HttpResponse};
+pub use options::{
+    CandidateScorer, DecodeOptions, FaviconService, FetchOptions, DEFAULT_ICON_RELS,
+    DEFAULT_MAX_FETCH_THREADS, EXCLUDED_ICON_RELS,
+};
+pub use spec::{parse_website_url, FaviconSpec};
+pub use stats::{FetchReport, FetchStats};
 
+pub mod batch;
 pub mod errors;
+pub mod http;
+pub mod options;
 mod scraper;
+pub mod spec;
+pub mod stats;
+#[cfg(test)]
+mod test_utils;
 
-#[derive(Debug, Clone)]
+/// The `Debug` output only summarizes the image. Use `bytes` and `image` for the full data.
+#[derive(Clone)]
 pub struct Favicon {
     url: Url,
     bytes: Vec<u8>,
-    image: DynamicImage,
+    format: Option<ImageFormat>,
+    image: OnceLock<DynamicImage>,
+    decode_options: DecodeOptions,
+    theme_color: Option<Rgba<u8>>,
+    mask_color: Option<Rgba<u8>>,
+    page_url: Option<Url>,
+    source: FaviconSource,
+}
+
+/// Where a fetched favicon comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FaviconSource {
+    /// Fetched from the website itself.
+    #[default]
+    Website,
+    /// Fetched from the configured `FaviconService` because the website yielded no favicon.
+    Service,
 }
 
 impl Favicon {
     /// Fetches a favicon from a URL and returns a new Favicon instance.
     /// The fetching algorithm selects the first valid favicon found on the page.
+    /// If the page yields no favicon, the same URL with `www.` added or stripped is tried.
     /// Custom client can be passed to the function. If omitted, a new client will be created.
+    /// The passed client is used for every request, so a client with a cookie store keeps its cookies.
+    #[cfg(feature = "reqwest")]
     pub fn fetch(url: Url, client: Option<Client>) -> Result<Self, FavilibError> {
-        let client = client.unwrap_or(Client::new());
-        Ok(scraper::fetch_and_validate_favicon(url.clone(), &client)?)
+        Self::fetch_with_options(url, client, &FetchOptions::default())
+    }
+
+    /// Fetches a favicon from a URL using the given fetch options.
+    /// Custom client can be passed to the function. If omitted, a new client will be created.
+    #[cfg(feature = "reqwest")]
+    pub fn fetch_with_options(
+        url: Url,
+        client: Option<Client>,
+        options: &FetchOptions,
+    ) -> Result<Self, FavilibError> {
+        let client = match client {
+            Some(client) => client,
+            None => scraper::build_client(options)?,
+        };
+        Self::fetch_with_client(url, &client, options)
+    }
+
+    /// Fetches a favicon from a URL using a custom HTTP backend.
+    pub fn fetch_with_client(
+        url: Url,
+        client: &dyn HttpClient,
+        options: &FetchOptions,
+    ) -> Result<Self, FavilibError> {
+        scraper::fetch_and_validate_favicon(url, client, options)
+    }
+
+    /// Fetches the favicon of a domain without a scheme, e.g. `example.com`, using a custom HTTP backend.
+    /// `https` is tried before `http`, each with the domain as given and with `www.` added or stripped.
+    /// The URL which yielded the favicon is its `page_url`.
+    pub fn fetch_by_domain_with_client(
+        domain: &str,
+        client: &dyn HttpClient,
+        options: &FetchOptions,
+    ) -> Result<Self, FavilibError> {
+        scraper::fetch_by_domain(domain, client, options)
+    }
+
+    /// Finds the URL of the favicon of a page without downloading the favicon.
+    /// Candidates are validated with HEAD requests in priority order, the first which responds with a 2xx status and
+    /// an image content type is returned. Servers which reject HEAD or send no content type get a ranged GET instead.
+    /// Linked pages, the `www.` and `http` variants and the favicon service are tried like in `fetch_with_client`,
+    /// but checks which need the decoded image, like `min_dimensions` and `reject_placeholders`, are not applied.
+    /// Returns `AllCandidatesFailedError` with the reason of each candidate if none is valid.
+    pub fn resolve_url(
+        url: Url,
+        client: &dyn HttpClient,
+        options: &FetchOptions,
+    ) -> Result<Url, FavilibError> {
+        scraper::resolve_favicon_url(url, client, options)
+    }
+
+    /// Iterates the favicon candidates of a page, ordered by `candidate_scorer` if it is set and as declared otherwise.
+    /// Candidates are only downloaded when the iterator yields them, so it can be stopped early.
+    /// Unlike `fetch_with_client`, canonical links and the `www.` variant are not followed.
+    pub fn fetch_iter<'a>(
+        url: Url,
+        client: &'a dyn HttpClient,
+        options: &'a FetchOptions,
+    ) -> impl Iterator<Item = Result<Self, FavilibError>> + 'a {
+        scraper::iter_favicons(url, client, options)
+    }
+
+    /// Fetches a favicon like `fetch_with_client` and returns the network statistics of the fetch.
+    /// The statistics are returned even if no favicon was found.
+    pub fn fetch_with_stats(
+        url: Url,
+        client: &dyn HttpClient,
+        options: &FetchOptions,
+    ) -> (Result<Self, FavilibError>, FetchStats) {
+        let stats = stats::StatsRecorder::default();
+        let result = scraper::fetch_and_validate_favicon_with_stats(url, client, options, &stats);
+        (result, stats.snapshot())
+    }
+
+    /// Fetches a favicon like `fetch_with_client`, but falls back to the largest candidate which was only rejected
+    /// for being smaller than `min_dimensions` instead of failing. Placeholders are never returned.
+    /// `quality_ok` tells whether the gates were met. The candidates are only fetched once.
+    /// The fallback has no page metadata like `page_url` or `theme_color`.
+    pub fn fetch_best_effort(
+        url: Url,
+        client: &dyn HttpClient,
+        options: &FetchOptions,
+    ) -> Result<BestEffortFavicon, FavilibError> {
+        let stats = stats::StatsRecorder::keeping_undersized();
+        match scraper::fetch_and_validate_favicon_with_stats(url, client, options, &stats) {
+            Ok(favicon) => Ok(BestEffortFavicon {
+                favicon,
+                quality_ok: true,
+            }),
+            Err(e) => match stats.largest_undersized() {
+                Some(favicon) => Ok(BestEffortFavicon {
+                    favicon,
+                    quality_ok: false,
+                }),
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Fetches a favicon like `fetch_with_client` and returns the redirects which led to the page and the favicon.
+    /// The report is empty if no favicon was found.
+    pub fn fetch_with_report(
+        url: Url,
+        client: &dyn HttpClient,
+        options: &FetchOptions,
+    ) -> (Result<Self, FavilibError>, FetchReport) {
+        let stats = stats::StatsRecorder::default();
+        let result = scraper::fetch_and_validate_favicon_with_stats(url, client, options, &stats);
+        let report = match &result {
+            Ok(favicon) => FetchReport {
+                page_redirects: favicon
+                    .page_url()
+                    .map(|url| stats.redirects_of(url))
+                    .unwrap_or_default(),
+                icon_redirects: stats.redirects_of(favicon.url()),
+            },
+            Err(_) => FetchReport::default(),
+        };
+        (result, report)
     }
 
     /// Builds a new Favicon instance from a URL and a byte vector.
     /// Does not fetch the image from the URL.
     /// Use the fetch function to fetch the image.
     pub fn build(url: Url, bytes: Vec<u8>) -> Result<Self, FavilibError> {
-        let image = ImageReader::new(Cursor::new(bytes.clone()))
-            .with_guessed_format()
-            .map(|img| img.decode())
-            .map_err(|_| FavilibError::NoFaviconFoundError)??;
+        Self::build_with_options(url, bytes, &DecodeOptions::default())
+    }
+
+    /// Builds a new Favicon instance from a local file.
+    /// The URL of the favicon is the `file://` URL of the canonicalized path.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, FavilibError> {
+        let path = std::fs::canonicalize(path)?;
+        let bytes = std::fs::read(&path)?;
+        let url = Url::from_file_path(&path)
+            .map_err(|_| FavilibError::UrlParseError(url::ParseError::RelativeUrlWithoutBase))?;
+        Self::build(url, bytes)
+    }
+
+    /// Builds a new Favicon instance from a URL and a byte vector using the given decode options.
+    /// The format is guessed from the bytes. If the bytes don't decode in the guessed format,
+    /// the common favicon formats are tried in turn. The error of the first attempt is returned if all fail.
+    /// Returns `ImageLimitsExceededError` if the image exceeds the configured limits.
+    pub fn build_with_options(
+        url: Url,
+        bytes: Vec<u8>,
+        options: &DecodeOptions,
+    ) -> Result<Self, FavilibError> {
+        let image = decode_bytes(&bytes, options)?;
+        Ok(Self {
+            decode_options: options.clone(),
+            ..Self::from_parts(url, bytes, image)
+        })
+    }
+
+    /// Builds a new Favicon instance without decoding the bytes.
+    /// Only the magic bytes are checked to belong to a known image format, the image is decoded on first access.
+    pub fn build_undecoded(url: Url, bytes: Vec<u8>) -> Result<Self, FavilibError> {
+        Self::build_undecoded_with_options(url, bytes, &DecodeOptions::default())
+    }
 
-        Ok(Self { url, bytes, image })
+    /// Same as `build_undecoded`, but the image is decoded with the given options on first access.
+    pub fn build_undecoded_with_options(
+        url: Url,
+        bytes: Vec<u8>,
+        options: &DecodeOptions,
+    ) -> Result<Self, FavilibError> {
+        let format = image::guess_format(&bytes)?;
+        Ok(Self {
+            url,
+            bytes,
+            format: Some(format),
+            image: OnceLock::new(),
+            decode_options: options.clone(),
+            theme_color: None,
+            mask_color: None,
+            page_url: None,
+            source: FaviconSource::Website,
+        })
+    }
+
+    /// Builds a new Favicon instance from already decoded parts without decoding the bytes.
+    /// The caller guarantees that `bytes` and `image` describe the same image.
+    /// `bytes` may also be the raw pixels of `image`, in which case no format is detected for them.
+    pub fn from_parts(url: Url, bytes: Vec<u8>, image: DynamicImage) -> Self {
+        let format = match bytes == image.as_bytes() {
+            true => None,
+            false => image::guess_format(&bytes).ok(),
+        };
+        Self {
+            url,
+            bytes,
+            format,
+            image: OnceLock::from(image),
+            decode_options: DecodeOptions::default(),
+            theme_color: None,
+            mask_color: None,
+            page_url: None,
+            source: FaviconSource::Website,
+        }
+    }
+
+    /// Splits the favicon into its URL, bytes and decoded image.
+    pub fn into_parts(mut self) -> (Url, Vec<u8>, DynamicImage) {
+        let image = self.take_image();
+        (self.url, self.bytes, image)
     }
 
     /// Crates a new instance with changed image size and image bytes.
+    /// Favicons whose bytes fail to decode are left unchanged, use `try_resize` to get the decode error instead.
+    /// Custom sizes with a zero dimension leave the image unchanged and dimensions above
+    /// `MAX_DIMENSION` are capped. Use `try_resize` to reject such sizes instead.
+    /// `ImageSize::Default` returns the favicon unchanged, keeping its original encoded bytes.
+    /// ICO sources with an embedded image of the requested dimensions use that image instead of scaling.
     pub fn resize(self, size: ImageSize) -> Favicon {
-        let img = self.image;
         let img = match size {
-            ImageSize::Small => img.resize_to_fill(16, 16, FilterType::Lanczos3),
-            ImageSize::Medium => img.resize_to_fill(32, 32, FilterType::Lanczos3),
-            ImageSize::Large => img.resize_to_fill(64, 64, FilterType::Lanczos3),
-            ImageSize::Custom(width, height) => {
-                img.resize_to_fill(width, height, FilterType::Lanczos3)
-            }
-            ImageSize::Default => img,
-            ImageSize::Invalid => img,
+            ImageSize::Default | ImageSize::Invalid => return self,
+            _ if self.try_image().is_err() => return self,
+            _ => self.resize_source(&size),
         };
 
         Self {
-            url: self.url,
             bytes: img.clone().into_bytes(),
-            image: img,
+            format: None,
+            image: OnceLock::from(img),
+            ..self
         }
     }
 
-    pub fn change_format(&self, format: ImageFormat) -> Result<Self> {
-        // TODO: Check for formats which do not support transparency.
-        // Eventually this function should not return a Result.
-        let mut buffer = Cursor::new(Vec::new());
+    /// Same as `resize`, but also reports how the image was scaled and cropped.
+    pub fn resize_with_report(self, size: ImageSize) -> (Favicon, ResizeReport) {
+        let original_dimensions = self.image().dimensions();
+        let resized = self.resize(size);
+        let report = ResizeReport::new(original_dimensions, resized.image().dimensions());
+        (resized, report)
+    }
 
-        self.image
-            .write_to(&mut buffer, format)
-            .context("Can't write image to bytes")?;
+    /// Crates a new instance with changed image size and image bytes.
+    /// Returns an error for invalid sizes, custom sizes with a zero dimension and
+    /// dimensions above `MAX_DIMENSION`, and the decode error if the bytes fail to decode.
+    pub fn try_resize(self, size: ImageSize) -> Result<Favicon, FavilibError> {
+        size.validate()?;
+        if size != ImageSize::Default {
+            self.try_image()?;
+        }
+        Ok(self.resize(size))
+    }
+
+    /// Same as `try_resize`, but multiplies the dimensions of `base` by a device pixel ratio first,
+    /// e.g. `Medium` at 2.0 resizes to 64x64 for `@2x` assets. `Default` multiplies the original dimensions.
+    /// Returns an error for ratios which are not positive and for sizes `try_resize` rejects.
+    pub fn resize_dpr(self, base: ImageSize, dpr: f32) -> Result<Favicon, FavilibError> {
+        if dpr.is_nan() || dpr <= 0.0 {
+            return Err(FavilibError::DevicePixelRatioError(dpr));
+        }
+        let (width, height) = match base {
+            ImageSize::Default => self.try_image()?.dimensions(),
+            base => base.dimensions().ok_or(FavilibError::SizeParseError)?,
+        };
+        let scaled = |value: u32| ((value as f64 * dpr as f64).round() as u32).max(1);
+        self.try_resize(ImageSize::Custom(scaled(width), scaled(height)))
+    }
 
-        let img = ImageReader::new(buffer)
-            .with_guessed_format()
-            .context("Can't determine file type")?
-            .decode()
-            .context("Can't decode image")?;
+    /// Creates a new resized instance while keeping the original.
+    /// Same as `resize`, but borrows instead of consuming the favicon.
+    pub fn resized(&self, size: ImageSize) -> Favicon {
+        match size {
+            ImageSize::Default | ImageSize::Invalid => self.clone(),
+            _ if self.try_image().is_err() => self.clone(),
+            _ => self.with_image(self.resize_source(&size)),
+        }
+    }
 
-        Ok(Self {
+    /// Creates a new instance for each of the given sizes, in the same order as `sizes`.
+    /// The source image is decoded once and shared across all resizes.
+    pub fn resize_to_multiple(&self, sizes: &[ImageSize]) -> Vec<Favicon> {
+        sizes
+            .iter()
+            .map(|size| self.resized(size.clone()))
+            .collect()
+    }
+
+    /// Creates a new instance whose smaller dimension is at least `min_dim`, preserving the aspect ratio.
+    /// Favicons which are already large enough are returned unchanged, including their original bytes.
+    /// Dimensions are capped at `MAX_DIMENSION`.
+    pub fn ensure_min_size(&self, min_dim: u32) -> Favicon {
+        let (width, height) = self.image().dimensions();
+        let smaller = width.min(height);
+        if smaller == 0 || smaller >= min_dim {
+            return self.clone();
+        }
+
+        let scale = min_dim as f64 / smaller as f64;
+        let scaled = |value: u32| ((value as f64 * scale).round() as u32).clamp(1, MAX_DIMENSION);
+        self.with_image(self.image().resize_exact(
+            scaled(width),
+            scaled(height),
+            FilterType::Lanczos3,
+        ))
+    }
+
+    /// Creates a new instance whose larger dimension is at most `max_dim`, preserving the aspect ratio.
+    /// Favicons which already fit are returned unchanged, including their original bytes, so nothing is upscaled.
+    pub fn cap_size(&self, max_dim: u32) -> Favicon {
+        let (width, height) = self.image().dimensions();
+        let larger = width.max(height);
+        if larger <= max_dim {
+            return self.clone();
+        }
+
+        let scale = max_dim as f64 / larger as f64;
+        let scaled = |value: u32| ((value as f64 * scale).round() as u32).max(1);
+        self.with_image(self.image().resize_exact(
+            scaled(width),
+            scaled(height),
+            FilterType::Lanczos3,
+        ))
+    }
+
+    /// Creates a new instance rotated by 90 degrees clockwise.
+    pub fn rotate90(&self) -> Favicon {
+        self.with_image(self.image().rotate90())
+    }
+
+    /// Creates a new instance rotated by 180 degrees.
+    pub fn rotate180(&self) -> Favicon {
+        self.with_image(self.image().rotate180())
+    }
+
+    /// Creates a new instance rotated by 270 degrees clockwise.
+    pub fn rotate270(&self) -> Favicon {
+        self.with_image(self.image().rotate270())
+    }
+
+    /// Creates a new instance mirrored along the vertical axis.
+    pub fn flip_horizontal(&self) -> Favicon {
+        self.with_image(self.image().fliph())
+    }
+
+    /// Creates a new instance mirrored along the horizontal axis.
+    pub fn flip_vertical(&self) -> Favicon {
+        self.with_image(self.image().flipv())
+    }
+
+    /// Creates a new instance centered on a square canvas filled with `bg`.
+    /// The canvas size is the larger dimension of the image, so nothing is cropped.
+    /// Use `Rgba([0, 0, 0, 0])` for a transparent background.
+    pub fn pad_to_square(&self, bg: Rgba<u8>) -> Favicon {
+        let (width, height) = self.image().dimensions();
+        let side = width.max(height);
+
+        let mut canvas = RgbaImage::from_pixel(side, side, bg);
+        let x = (side - width) / 2;
+        let y = (side - height) / 2;
+        imageops::overlay(&mut canvas, &self.image().to_rgba8(), x as i64, y as i64);
+
+        self.with_image(DynamicImage::ImageRgba8(canvas))
+    }
+
+    /// Returns the fraction of fully transparent pixels between 0.0 and 1.0.
+    /// Images without pixels count as fully transparent.
+    pub fn transparency_ratio(&self) -> f32 {
+        let (width, height) = self.image().dimensions();
+        let total = width as u64 * height as u64;
+        if total == 0 {
+            return 1.0;
+        }
+        let transparent = self
+            .image()
+            .pixels()
+            .filter(|(_, _, pixel)| pixel[3] == 0)
+            .count();
+        transparent as f32 / total as f32
+    }
+
+    /// Whether the image has an alpha channel with at least one pixel which is not fully opaque.
+    pub fn has_transparency(&self) -> bool {
+        self.image().color().has_alpha()
+            && self.image().pixels().any(|(_, _, pixel)| pixel[3] < 255)
+    }
+
+    /// Suggests an output format based on the content of the image.
+    /// Transparent icons and opaque icons with few colors are best kept lossless as PNG.
+    /// Opaque images with more than 256 distinct colors, e.g. photos, are suggested as JPEG.
+    pub fn recommended_format(&self) -> ImageFormat {
+        const MAX_FLAT_COLORS: usize = 256;
+
+        if self.has_transparency() {
+            return ImageFormat::Png;
+        }
+        let mut colors = std::collections::HashSet::new();
+        for (_, _, pixel) in self.image().pixels() {
+            colors.insert(pixel);
+            if colors.len() > MAX_FLAT_COLORS {
+                return ImageFormat::Jpeg;
+            }
+        }
+        ImageFormat::Png
+    }
+
+    /// Renders the image as colored Unicode half blocks for a terminal with 24-bit color support.
+    /// The image is scaled to `cols` characters per line, each character shows two vertically stacked pixels.
+    /// Mostly transparent pixels show the terminal background. Every line ends with a reset and a newline.
+    pub fn to_ansi_preview(&self, cols: u32) -> String {
+        let (width, height) = self.image().dimensions();
+        if cols == 0 || width == 0 || height == 0 {
+            return String::new();
+        }
+
+        let rows = ((cols as f64 * height as f64 / width as f64 / 2.0).round() as u32).max(1);
+        let image = self
+            .image()
+            .resize_exact(cols, rows * 2, FilterType::Triangle)
+            .to_rgba8();
+        let visible = |pixel: &Rgba<u8>| pixel[3] >= 128;
+
+        let mut preview = String::new();
+        for row in 0..rows {
+            for x in 0..cols {
+                let top = image.get_pixel(x, row * 2);
+                let bottom = image.get_pixel(x, row * 2 + 1);
+                let cell = match (visible(top), visible(bottom)) {
+                    (true, true) => format!(
+                        "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                        top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+                    ),
+                    (true, false) => {
+                        format!(
+                            "\x1b[49m\x1b[38;2;{};{};{}m\u{2580}",
+                            top[0], top[1], top[2]
+                        )
+                    }
+                    (false, true) => format!(
+                        "\x1b[49m\x1b[38;2;{};{};{}m\u{2584}",
+                        bottom[0], bottom[1], bottom[2]
+                    ),
+                    (false, false) => "\x1b[0m ".to_string(),
+                };
+                preview.push_str(&cell);
+            }
+            preview.push_str("\x1b[0m\n");
+        }
+        preview
+    }
+
+    /// Compares the favicon with another one and returns a similarity score between 0.0 and 1.0.
+    /// Both images are scaled to 16x16 pixels. The score is one minus the mean squared error of
+    /// their RGBA channels, normalized to the channel range. Identical images score 1.0.
+    /// The score is symmetric and ignores the original dimensions.
+    pub fn similarity(&self, other: &Favicon) -> f32 {
+        const SIZE: u32 = 16;
+        let scale = |img: &DynamicImage| {
+            img.resize_exact(SIZE, SIZE, FilterType::Triangle)
+                .to_rgba8()
+        };
+        let (a, b) = (scale(self.image()), scale(other.image()));
+
+        let squared_error: f64 = a
+            .as_raw()
+            .iter()
+            .zip(b.as_raw())
+            .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+            .sum();
+        let mse = squared_error / a.as_raw().len() as f64;
+
+        (1.0 - mse / (255.0 * 255.0)) as f32
+    }
+
+    /// Creates a new instance from the same URL with a transformed image.
+    fn with_image(&self, image: DynamicImage) -> Favicon {
+        Self {
             url: self.url.clone(),
-            bytes: img.clone().into_bytes(),
-            image: img,
-        })
+            bytes: image.clone().into_bytes(),
+            format: None,
+            image: OnceLock::from(image),
+            decode_options: self.decode_options.clone(),
+            theme_color: self.theme_color,
+            mask_color: self.mask_color,
+            page_url: self.page_url.clone(),
+            source: self.source,
+        }
     }
 
-    /// Writes the images bytes to stdout.
-    pub fn write_to_stdout(&self, format: ImageFormat) -> Result<(), FavilibError> {
-        let mut buffer = Cursor::new(Vec::new());
+    /// Creates a new instance from the same URL with other encoded bytes, which are decoded on first access.
+    fn with_bytes(&self, bytes: Vec<u8>) -> Favicon {
+        Self {
+            url: self.url.clone(),
+            format: image::guess_format(&bytes).ok(),
+            bytes,
+            image: OnceLock::new(),
+            decode_options: self.decode_options.clone(),
+            theme_color: self.theme_color,
+            mask_color: self.mask_color,
+            page_url: self.page_url.clone(),
+            source: self.source,
+        }
+    }
 
-        self.image
-            .write_to(&mut buffer, format)
-            .context("Can't write image to stdout")?;
+    /// Converts the favicon to the given format.
+    /// The bytes are encoded in the new format and the image is only decoded from them again when it is accessed,
+    /// so exporting in the new format writes the bytes unchanged without a decode. The original bytes are kept if
+    /// they are already encoded in the given format.
+    /// Animated GIF, WebP and APNG sources keep all frames when converted to GIF, within the decoding limits.
+    /// Other formats, including WebP since `image` can't encode animated WebP, are flattened to the first frame.
+    pub fn change_format(&self, format: ImageFormat) -> Result<Self> {
+        if format == ImageFormat::Gif {
+            if let Some(frames) = self.animation_frames()? {
+                let mut bytes = Vec::new();
+                {
+                    let mut encoder = GifEncoder::new(&mut bytes);
+                    encoder
+                        .set_repeat(Repeat::Infinite)
+                        .context("Can't write image to bytes")?;
+                    encoder
+                        .encode_frames(frames)
+                        .context("Can't write image to bytes")?;
+                }
+                return Ok(self.with_bytes(bytes));
+            }
+        }
+
+        if self.format_of_bytes() == Some(format) {
+            return Ok(self.clone());
+        }
+        Ok(self.with_bytes(self.encoded_bytes(format)?))
+    }
 
+    /// Writes the images bytes to stdout.
+    /// The original bytes are written unchanged if they are already encoded in the given format.
+    pub fn write_to_stdout(&self, format: ImageFormat) -> Result<(), FavilibError> {
         let stdout = io::stdout();
         let mut handle = stdout.lock();
-        handle.write_all(buffer.get_ref())?;
+        self.write_to(&mut handle, format)
+    }
 
+    /// Writes the images bytes to the given writer, e.g. a buffer or an HTTP response.
+    /// The original bytes are written unchanged if they are already encoded in the given format.
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        format: ImageFormat,
+    ) -> Result<(), FavilibError> {
+        let bytes = self.encoded_bytes(format)?;
+        writer.write_all(&bytes)?;
         Ok(())
     }
 
+    /// Encodes the image as PNG.
+    /// The original bytes are returned unchanged if they are already PNG encoded.
+    pub fn as_png_bytes(&self) -> Result<Vec<u8>, FavilibError> {
+        self.encoded_bytes(ImageFormat::Png)
+    }
+
+    /// Encodes the image as lossless WebP.
+    /// The original bytes are returned unchanged if they are already WebP encoded.
+    pub fn as_webp_bytes(&self) -> Result<Vec<u8>, FavilibError> {
+        self.encoded_bytes(ImageFormat::WebP)
+    }
+
+    /// Number of bytes `export` would write in the given format, without writing a file.
+    pub fn encoded_size(&self, format: ImageFormat) -> Result<usize, FavilibError> {
+        Ok(self.encoded_bytes(format)?.len())
+    }
+
+    /// Encodes the image as a `data:` URI.
+    /// The original bytes are used unchanged if they are already encoded in the given format.
+    pub fn to_data_uri(&self, format: ImageFormat) -> Result<String, FavilibError> {
+        Ok(format!(
+            "data:{};base64,{}",
+            format.to_mime_type(),
+            BASE64_STANDARD.encode(self.encoded_bytes(format)?)
+        ))
+    }
+
+    /// Builds a `<link rel="icon">` tag which embeds the image as a `data:` URI.
+    pub fn to_html_link(&self, format: ImageFormat) -> Result<String, FavilibError> {
+        Ok(format!(
+            r#"<link rel="icon" type="{}" href="{}">"#,
+            format.to_mime_type(),
+            self.to_data_uri(format)?
+        ))
+    }
+
+    /// Builds a CSS rule which sets the image as `background-image` of the given selector.
+    pub fn to_css_background(
+        &self,
+        selector: &str,
+        format: ImageFormat,
+    ) -> Result<String, FavilibError> {
+        Ok(format!(
+            r#"{} {{ background-image: url("{}"); }}"#,
+            selector,
+            self.to_data_uri(format)?
+        ))
+    }
+
+    /// Encodes the image in each of the given formats, e.g. PNG and WebP for content negotiation.
+    /// The image is decoded once and encoded once per format, original bytes are reused for their own format.
+    /// The error names the first format which failed to encode.
+    pub fn to_formats(
+        &self,
+        formats: &[ImageFormat],
+    ) -> Result<Vec<(ImageFormat, Vec<u8>)>, FavilibError> {
+        formats
+            .iter()
+            .map(|&format| {
+                let bytes = self
+                    .encoded_bytes(format)
+                    .with_context(|| format!("Can't encode image as {:?}", format))?;
+                Ok((format, bytes))
+            })
+            .collect()
+    }
+
+    /// Returns the original bytes if they are encoded in the given format, otherwise encodes the image.
+    fn encoded_bytes(&self, format: ImageFormat) -> Result<Vec<u8>, FavilibError> {
+        if self.format_of_bytes() == Some(format) {
+            return Ok(self.bytes.clone());
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        self.try_image()?
+            .write_to(&mut buffer, format)
+            .context("Can't encode image")?;
+        Ok(buffer.into_inner())
+    }
+
     /// Exports the image to a file at the given path.
+    /// The original bytes are written unchanged if they are already encoded in the given format.
+    /// The file extension is not checked, use `export_with_check` to reject one which does not match the format.
     pub fn export<Q>(&self, path: Q, format: ImageFormat) -> Result<(), FavilibError>
     where
         Q: AsRef<Path>,
     {
-        self.image.save_with_format(path, format).context(format!(
-            "Failed to save image from domain {}",
-            self.url.as_str()
-        ))?;
+        self.export_with_check(path, format, ExtensionCheck::Ignore)
+    }
+
+    /// Same as `export`, but creates the missing parent directories of the path first.
+    pub fn export_create_dirs<Q>(&self, path: Q, format: ImageFormat) -> Result<(), FavilibError>
+    where
+        Q: AsRef<Path>,
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.export(path, format)
+    }
+
+    /// Writes a classic `favicon.ico` with the sizes of `ICO_FAVICON_SET_SIZES` to the given path.
+    /// Each size is scaled from the smallest image embedded in an ICO source which is at least as large.
+    /// Other sources and sizes without such an image are scaled from the decoded image.
+    pub fn save_ico_favicon_set<Q>(&self, path: Q) -> Result<(), FavilibError>
+    where
+        Q: AsRef<Path>,
+    {
+        let mut dir = ico::IconDir::new(ico::ResourceType::Icon);
+        for size in ICO_FAVICON_SET_SIZES {
+            let image = self
+                .ico_source_for(size)
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .to_rgba8();
+            let icon = ico::IconImage::from_rgba_data(size, size, image.into_raw());
+            dir.add_entry(ico::IconDirEntry::encode(&icon)?);
+        }
+
+        let file = std::fs::File::create(path)?;
+        dir.write(io::BufWriter::new(file))?;
+        Ok(())
+    }
+
+    /// Exports the image to a file at the given path.
+    /// The given check decides what happens if the file extension does not match the format.
+    pub fn export_with_check<Q>(
+        &self,
+        path: Q,
+        format: ImageFormat,
+        check: ExtensionCheck,
+    ) -> Result<(), FavilibError>
+    where
+        Q: AsRef<Path>,
+    {
+        let extension_format = ImageFormat::from_path(path.as_ref()).ok();
+
+        if extension_format.is_some_and(|extension_format| extension_format != format) {
+            match check {
+                ExtensionCheck::Ignore => {}
+                ExtensionCheck::Deny => return Err(FavilibError::FormatMismatchError(format)),
+            }
+        }
+
+        if self.format_of_bytes() == Some(format) {
+            std::fs::write(path, &self.bytes)?;
+            return Ok(());
+        }
+
+        self.try_image()?
+            .save_with_format(path, format)
+            .context(format!(
+                "Failed to save image from domain {}",
+                self.url.as_str()
+            ))?;
         Ok(())
     }
 
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
     }
-    pub fn image(&self) -> &DynamicImage {
-        &self.image
+
+    /// Format of the stored bytes, detected from their magic bytes when the favicon was built.
+    /// Returns `None` if the bytes are raw pixels, e.g. after resizing, even if they happen to start like an encoded image.
+    pub fn format_of_bytes(&self) -> Option<ImageFormat> {
+        self.format
     }
 
-    /// Exact URL of the favicon including it's path.
-    pub fn url(&self) -> &Url {
-        &self.url
+    /// Checks that the bytes decode to an image with non-zero dimensions.
+    /// Intended for favicons built from external bytes, e.g. via `from_parts`.
+    /// The bytes are decoded with the `DecodeOptions` of the favicon, so images exceeding their limits
+    /// fail with `ImageLimitsExceededError`.
+    pub fn validate(&self) -> Result<(), FavilibError> {
+        self.validate_formats(&[])
     }
-}
 
-/// Fetches a favicon from a URL and saves it to a file at the given path.
-pub fn fetch<Q>(
-    url: Url,
-    image_size: ImageSize,
-    format: ImageFormat,
-    path: &Q,
-    client: Option<Client>,
-) -> Result<(), FavilibError>
-where
-    Q: AsRef<Path>,
-{
-    let client = client.unwrap_or(Client::new());
-    let favicon = Favicon::fetch(url, Some(client))?;
-    let favicon = favicon.resize(image_size);
-    favicon.export(&path, format)?;
-    Ok(())
-}
+    /// Same as `validate`, but also checks that the bytes are encoded in one of the allowed formats.
+    /// An empty list allows all formats.
+    pub fn validate_formats(&self, allowed: &[ImageFormat]) -> Result<(), FavilibError> {
+        let format = image::guess_format(&self.bytes)?;
+        if !allowed.is_empty() && !allowed.contains(&format) {
+            return Err(FavilibError::FormatNotAllowedError(format));
+        }
 
-/// Represents the size of the image to be fetched.
-/// Default values are: Small (16x16), Medium (32x32), Large (64x64).
-/// Custom allows for custom sizes to be set.
-/// Default uses the original size of the image.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub enum ImageSize {
-    Small,
-    Medium,
-    Large,
-    Custom(u32, u32),
-    Default,
-    Invalid,
-}
+        let image = decode_image(&self.bytes, format, &self.decode_options)?;
+        if image.width() == 0 || image.height() == 0 {
+            return Err(FavilibError::EmptyImageError);
+        }
+        Ok(())
+    }
 
-impl From<&str> for ImageSize {
-    fn from(s: &str) -> Self {
-        match s {
-            "small" => ImageSize::Small,
-            "medium" => ImageSize::Medium,
-            "large" => ImageSize::Large,
-            "default" => ImageSize::Default,
-            _ => {
-                let parts: Vec<&str> = s.split(',').collect();
-                if parts.len() != 2 {
-                    return ImageSize::Invalid;
-                }
-                let width = parts[0].parse();
-                let height = parts[1].parse();
+    /// Dimensions of the images embedded in an ICO favicon.
+    /// Returns an empty list if the favicon is not an ICO file.
+    pub fn ico_entries(&self) -> Vec<(u32, u32)> {
+        self.ico_dir()
+            .map(|dir| {
+                dir.entries()
+                    .iter()
+                    .map(|entry| (entry.width(), entry.height()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-                if width.is_err() || height.is_err() {
-                    return ImageSize::Invalid;
+    /// Creates a new instance from the image embedded in an ICO favicon with the given dimensions.
+    /// By default the decoder picks a single embedded image, use `ico_entries` to list the available ones.
+    pub fn select_ico_entry(&self, width: u32, height: u32) -> Result<Favicon, FavilibError> {
+        Ok(self.with_image(self.decode_ico_entry(width, height)?))
+    }
+
+    /// Decodes the image embedded in an ICO favicon with the given dimensions.
+    fn decode_ico_entry(&self, width: u32, height: u32) -> Result<DynamicImage, FavilibError> {
+        let entry = self
+            .ico_dir()
+            .and_then(|dir| {
+                dir.entries()
+                    .iter()
+                    .find(|entry| entry.width() == width && entry.height() == height)
+                    .cloned()
+            })
+            .ok_or(FavilibError::IcoEntryNotFoundError(width, height))?;
+
+        let icon = entry.decode()?;
+        let image = RgbaImage::from_raw(icon.width(), icon.height(), icon.rgba_data().to_vec())
+            .ok_or(FavilibError::IcoEntryNotFoundError(width, height))?;
+        Ok(DynamicImage::ImageRgba8(image))
+    }
+
+    /// Image for the given size. ICO sources use an embedded image with the exact dimensions if there is one,
+    /// since it is crisper than a scaled one. Other sources and sizes are scaled from the decoded image.
+    fn resize_source(&self, size: &ImageSize) -> DynamicImage {
+        size.dimensions()
+            .and_then(|(width, height)| self.decode_ico_entry(width, height).ok())
+            .unwrap_or_else(|| resize_image(self.image(), size))
+    }
+
+    /// Decodes all frames of an animated source. Returns `None` for static sources.
+    /// The dimension limits of the `DecodeOptions` apply to the animation and its `max_alloc` to all frames together.
+    /// Returns `ImageLimitsExceededError` if they or `MAX_ANIMATION_FRAMES` are exceeded.
+    fn animation_frames(&self) -> Result<Option<Vec<Frame>>, FavilibError> {
+        let reader = Cursor::new(self.bytes.as_slice());
+        let limits = self.decode_options.limits();
+        let frames = match self.format_of_bytes() {
+            Some(ImageFormat::Gif) => {
+                let mut decoder = GifDecoder::new(reader).map_err(map_decode_error)?;
+                decoder.set_limits(limits).map_err(map_decode_error)?;
+                decoder.into_frames()
+            }
+            Some(ImageFormat::WebP) => {
+                let mut decoder = WebPDecoder::new(reader).map_err(map_decode_error)?;
+                if !decoder.has_animation() {
+                    return Ok(None);
+                }
+                decoder.set_limits(limits).map_err(map_decode_error)?;
+                decoder.into_frames()
+            }
+            Some(ImageFormat::Png) => {
+                let decoder = PngDecoder::with_limits(reader, limits).map_err(map_decode_error)?;
+                if !decoder.is_apng().map_err(map_decode_error)? {
+                    return Ok(None);
                 }
+                decoder.apng().map_err(map_decode_error)?.into_frames()
+            }
+            _ => return Ok(None),
+        };
 
-                ImageSize::Custom(width.unwrap(), height.unwrap())
+        let mut collected = Vec::new();
+        let mut total_bytes = 0;
+        for frame in frames {
+            let frame = frame.map_err(map_decode_error)?;
+            total_bytes += frame.buffer().len() as u64;
+            if collected.len() == MAX_ANIMATION_FRAMES
+                || total_bytes > self.decode_options.max_alloc
+            {
+                return Err(FavilibError::ImageLimitsExceededError);
             }
+            collected.push(frame);
+        }
+        Ok(Some(collected).filter(|frames| frames.len() > 1))
+    }
+
+    /// Smallest embedded ICO image with both dimensions of at least `size`, or the decoded image.
+    fn ico_source_for(&self, size: u32) -> DynamicImage {
+        self.ico_dir()
+            .and_then(|dir| {
+                dir.entries()
+                    .iter()
+                    .filter(|entry| entry.width() >= size && entry.height() >= size)
+                    .min_by_key(|entry| entry.width() as u64 * entry.height() as u64)
+                    .and_then(|entry| entry.decode().ok())
+            })
+            .and_then(|icon| {
+                RgbaImage::from_raw(icon.width(), icon.height(), icon.rgba_data().to_vec())
+            })
+            .map(DynamicImage::ImageRgba8)
+            .unwrap_or_else(|| self.image().clone())
+    }
+
+    fn ico_dir(&self) -> Option<ico::IconDir> {
+        if self.format_of_bytes() != Some(ImageFormat::Ico) {
+            return None;
+        }
+        ico::IconDir::read(Cursor::new(&self.bytes)).ok()
+    }
+
+    /// Decoded image of the favicon.
+    /// Favicons built with `build_undecoded` are decoded with their `DecodeOptions` on the first call.
+    /// Bytes which fail to decode yield an empty image, use `try_image` to get the error instead.
+    pub fn image(&self) -> &DynamicImage {
+        static EMPTY_IMAGE: OnceLock<DynamicImage> = OnceLock::new();
+
+        self.try_image()
+            .unwrap_or_else(|_| EMPTY_IMAGE.get_or_init(DynamicImage::default))
+    }
+
+    /// Same as `image`, but returns the error if the bytes fail to decode or exceed the decoding limits.
+    /// Failed decodes are not cached, so every call tries again.
+    pub fn try_image(&self) -> Result<&DynamicImage, FavilibError> {
+        if let Some(image) = self.image.get() {
+            return Ok(image);
         }
+        let image = decode_bytes(&self.bytes, &self.decode_options)?;
+        Ok(self.image.get_or_init(|| image))
+    }
+
+    /// Whether the image has been decoded. Only favicons built with `build_undecoded` start undecoded.
+    pub fn is_decoded(&self) -> bool {
+        self.image.get().is_some()
+    }
+
+    /// Moves the decoded image out of the favicon, decoding it first if necessary.
+    fn take_image(&mut self) -> DynamicImage {
+        self.image();
+        self.image.take().unwrap_or_default()
+    }
+
+    /// Converts the image to an RGBA buffer with 8 bits per channel.
+    pub fn to_rgba8(&self) -> RgbaImage {
+        self.image().to_rgba8()
+    }
+
+    /// Converts the image to an RGB buffer with 8 bits per channel. The alpha channel is dropped.
+    pub fn to_rgb8(&self) -> RgbImage {
+        self.image().to_rgb8()
+    }
+
+    /// Color of the `theme-color` meta tag of the page the favicon was fetched from.
+    /// Returns `None` if the tag is absent or its color can't be parsed.
+    pub fn theme_color(&self) -> Option<Rgba<u8>> {
+        self.theme_color
+    }
+
+    /// Color of the `color` attribute of the `mask-icon` link the favicon was fetched from.
+    /// Safari fills the monochrome mask icon of pinned tabs with it. Returns `None` for other candidates.
+    /// The color is only exposed, not applied: SVG mask icons can't be decoded, so there is no rasterization to fill.
+    pub fn mask_color(&self) -> Option<Rgba<u8>> {
+        self.mask_color
+    }
+
+    /// URL of the page the favicon was found on.
+    /// Differs from the requested URL if the page was only reachable with or without `www.`.
+    /// Returns `None` if the favicon was not fetched from a page.
+    pub fn page_url(&self) -> Option<&Url> {
+        self.page_url.as_ref()
+    }
+
+    /// Whether the favicon was fetched from the website or from a `FaviconService`.
+    pub fn source(&self) -> FaviconSource {
+        self.source
+    }
+
+    /// Exact URL of the favicon including it's path.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Replaces the URL of the favicon, e.g. after loading it from a cache.
+    /// The bytes and the image are kept, nothing is fetched or decoded again.
+    pub fn set_url(&mut self, url: Url) {
+        self.url = url;
+    }
+
+    /// Same as `set_url`, but consumes and returns the favicon for chaining.
+    pub fn with_url(mut self, url: Url) -> Favicon {
+        self.set_url(url);
+        self
+    }
+}
+
+/// Dimensions are only printed once the image is decoded, so formatting never triggers a decode.
+impl fmt::Debug for Favicon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Favicon");
+        debug.field("url", &self.url.as_str());
+        match self.image.get() {
+            Some(image) => debug.field("dimensions", &image.dimensions()),
+            None => debug.field("dimensions", &format_args!("undecoded")),
+        };
+        debug
+            .field("format", &self.format_of_bytes())
+            .field("bytes", &self.bytes.len())
+            .field("theme_color", &self.theme_color)
+            .field("mask_color", &self.mask_color)
+            .field("page_url", &self.page_url.as_ref().map(Url::as_str))
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// Prints e.g. `favicon<32x32 png> from https://example.com/favicon.png (1234 bytes)`.
+/// The format is the file extension of the stored bytes, or `raw` if they are raw pixels.
+/// The dimensions are printed as `?x?` until the image is decoded, so formatting never triggers a decode.
+impl fmt::Display for Favicon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dimensions = match self.image.get() {
+            Some(image) => format!("{}x{}", image.width(), image.height()),
+            None => "?x?".to_string(),
+        };
+        let format = self
+            .format_of_bytes()
+            .and_then(|format| format.extensions_str().first())
+            .unwrap_or(&"raw");
+        write!(
+            f,
+            "favicon<{} {}> from {} ({} bytes)",
+            dimensions,
+            format,
+            self.url,
+            self.bytes.len()
+        )
+    }
+}
+
+/// Decodes the bytes in the guessed format. If they don't decode in the guessed format,
+/// the common favicon formats are tried in turn. The error of the first attempt is returned if all fail.
+fn decode_bytes(bytes: &[u8], options: &DecodeOptions) -> Result<DynamicImage, FavilibError> {
+    const FALLBACK_FORMATS: [ImageFormat; 5] = [
+        ImageFormat::Png,
+        ImageFormat::Ico,
+        ImageFormat::Gif,
+        ImageFormat::Jpeg,
+        ImageFormat::WebP,
+    ];
+
+    let guessed = image::guess_format(bytes).ok();
+    let formats = guessed.into_iter().chain(
+        FALLBACK_FORMATS
+            .into_iter()
+            .filter(|&format| Some(format) != guessed),
+    );
+
+    let mut first_error = None;
+    for format in formats {
+        match decode_image(bytes, format, options) {
+            Ok(image) => return Ok(image),
+            Err(FavilibError::ImageLimitsExceededError) => {
+                return Err(FavilibError::ImageLimitsExceededError)
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(first_error.unwrap_or(FavilibError::NoFaviconFoundError))
+}
+
+fn decode_image(
+    bytes: &[u8],
+    format: ImageFormat,
+    options: &DecodeOptions,
+) -> Result<DynamicImage, FavilibError> {
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(options.limits());
+
+    let mut decoder = reader.into_decoder().map_err(map_decode_error)?;
+    if decoder.total_bytes() > options.max_alloc {
+        return Err(FavilibError::ImageLimitsExceededError);
+    }
+
+    let orientation = decoder.orientation().map_err(map_decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(map_decode_error)?;
+    if options.apply_orientation {
+        image.apply_orientation(orientation);
+    }
+    Ok(image)
+}
+
+fn map_decode_error(error: ImageError) -> FavilibError {
+    match error {
+        ImageError::Limits(_) => FavilibError::ImageLimitsExceededError,
+        e => FavilibError::ImageDecodeError(e),
+    }
+}
+
+/// Images without pixels, e.g. the empty image of bytes which failed to decode, are returned unchanged.
+fn resize_image(img: &DynamicImage, size: &ImageSize) -> DynamicImage {
+    if img.width() == 0 || img.height() == 0 {
+        return img.clone();
+    }
+    match *size {
+        ImageSize::Small => img.resize_to_fill(16, 16, FilterType::Lanczos3),
+        ImageSize::Medium => img.resize_to_fill(32, 32, FilterType::Lanczos3),
+        ImageSize::Large => img.resize_to_fill(64, 64, FilterType::Lanczos3),
+        ImageSize::Custom(0, _) | ImageSize::Custom(_, 0) => img.clone(),
+        ImageSize::Custom(width, height) => img.resize_to_fill(
+            width.min(MAX_DIMENSION),
+            height.min(MAX_DIMENSION),
+            FilterType::Lanczos3,
+        ),
+        ImageSize::Default | ImageSize::Invalid => img.clone(),
+    }
+}
+
+/// Fetches the favicon of a domain without a scheme, e.g. `example.com`, probing `https` and `http` with and
+/// without `www.`. See `Favicon::fetch_by_domain_with_client` for the order.
+#[cfg(feature = "reqwest")]
+pub fn fetch_by_domain(domain: &str, options: &FetchOptions) -> Result<Favicon, FavilibError> {
+    let client = scraper::build_client(options)?;
+    Favicon::fetch_by_domain_with_client(domain, &client, options)
+}
+
+/// Builds the client favilib uses when no custom client is passed, configured with the client options of `options`.
+/// Build it once to reuse it across fetches. Redirects are left to favilib, so fetches report them.
+#[cfg(feature = "reqwest")]
+pub fn default_client(options: &FetchOptions) -> Result<Client, FavilibError> {
+    scraper::build_client(options)
+}
+
+/// Same as `default_client`, but returns the configured builder so further settings can be added,
+/// e.g. a timeout or a user agent.
+#[cfg(feature = "reqwest")]
+pub fn default_client_builder(options: &FetchOptions) -> reqwest::blocking::ClientBuilder {
+    scraper::client_builder(options)
+}
+
+/// Packs the favicons into a grid with `columns` cells per row, e.g. for a dashboard showing many sites.
+/// Each favicon is resized to fill a cell of `cell` pixels. Cells without a favicon stay transparent.
+/// Returns the sprite sheet and the top-left coordinate of each favicon, in the same order as `favicons`.
+/// A `columns` value of 0 is treated as 1.
+/// Returns `SizeTooLargeError` if the sheet would be wider or higher than `MAX_DIMENSION`,
+/// and the decode error of the first favicon whose bytes fail to decode.
+pub fn make_sprite_sheet(
+    favicons: &[Favicon],
+    cell: (u32, u32),
+    columns: u32,
+) -> Result<(DynamicImage, Vec<(u32, u32)>), FavilibError> {
+    let (cell_width, cell_height) = cell;
+    let columns = columns.max(1);
+    let rows = u32::try_from(favicons.len())
+        .map_err(|_| FavilibError::SizeTooLargeError(MAX_DIMENSION))?
+        .div_ceil(columns);
+
+    let checked_length = |count: u32, length: u32| {
+        count
+            .checked_mul(length)
+            .filter(|&total| total <= MAX_DIMENSION)
+            .ok_or(FavilibError::SizeTooLargeError(MAX_DIMENSION))
+    };
+    let mut sheet = RgbaImage::new(
+        checked_length(columns, cell_width)?,
+        checked_length(rows, cell_height)?,
+    );
+    let mut positions = Vec::with_capacity(favicons.len());
+    for (index, favicon) in favicons.iter().enumerate() {
+        let index = index as u32;
+        let (x, y) = (
+            (index % columns) * cell_width,
+            (index / columns) * cell_height,
+        );
+        let icon = resize_image(
+            favicon.try_image()?,
+            &ImageSize::Custom(cell_width, cell_height),
+        );
+        imageops::overlay(&mut sheet, &icon.to_rgba8(), x as i64, y as i64);
+        positions.push((x, y));
+    }
+
+    Ok((DynamicImage::ImageRgba8(sheet), positions))
+}
+
+/// Fetches a favicon from a URL and saves it to a file at the given path.
+#[cfg(feature = "reqwest")]
+pub fn fetch<Q>(
+    url: Url,
+    image_size: ImageSize,
+    format: ImageFormat,
+    path: &Q,
+    client: Option<Client>,
+) -> Result<(), FavilibError>
+where
+    Q: AsRef<Path>,
+{
+    let favicon = fetch_resized(url, image_size, client)?;
+    favicon.export(path, format)?;
+    Ok(())
+}
+
+/// Fetches a favicon from a URL and writes it to the given writer in the given format.
+/// Same as `fetch`, but for callers streaming into a buffer or a response instead of a file.
+#[cfg(feature = "reqwest")]
+pub fn fetch_to_writer<W: Write>(
+    url: Url,
+    image_size: ImageSize,
+    format: ImageFormat,
+    writer: &mut W,
+    client: Option<Client>,
+) -> Result<(), FavilibError> {
+    let favicon = fetch_resized(url, image_size, client)?;
+    favicon.write_to(writer, format)
+}
+
+#[cfg(feature = "reqwest")]
+fn fetch_resized(
+    url: Url,
+    image_size: ImageSize,
+    client: Option<Client>,
+) -> Result<Favicon, FavilibError> {
+    let client = client.unwrap_or_default();
+    let favicon = Favicon::fetch(url, Some(client))?;
+    Ok(favicon.resize(image_size))
+}
+
+/// Collects the candidate favicon URLs declared in the given HTML without fetching them.
+/// Relative links are resolved against `base_url`. The `/favicon.ico` fallback is always included last.
+pub fn discover_favicon_urls(html: &str, base_url: Url) -> Vec<Url> {
+    discover_favicon_urls_with_options(html, base_url, &FetchOptions::default())
+}
+
+/// Collects the candidate favicon URLs declared in the given HTML using the given fetch options.
+pub fn discover_favicon_urls_with_options(
+    html: &str,
+    base_url: Url,
+    options: &FetchOptions,
+) -> Vec<Url> {
+    scraper::discover_favicon_urls(html.to_string(), base_url, options)
+}
+
+/// Ranks the candidate favicon URLs declared in the given HTML without fetching them.
+/// Candidates are returned in the order they are tried, highest score first, or ordered by `candidate_scorer`.
+pub fn rank_favicon_candidates(
+    html: &str,
+    base_url: Url,
+    options: &FetchOptions,
+) -> Vec<ScoredCandidate> {
+    scraper::rank_candidates(html.to_string(), base_url, options)
+}
+
+/// Favicon candidate of a page with the priority favilib assigns to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoredCandidate {
+    pub url: Url,
+
+    /// `rel` attribute of the link declaring the candidate.
+    /// `None` if it is not declared by a link, e.g. the `/favicon.ico` fallback or a meta tag.
+    pub rel: Option<String>,
+
+    /// Unparsed `sizes` attribute of the link declaring the candidate, e.g. `32x32` or `any`.
+    pub sizes: Option<String>,
+
+    /// Priority of the candidate, higher is preferred. Candidates are preferred in the order they are declared,
+    /// with preload images and the `/favicon.ico` fallback last, so the score reflects that position.
+    pub score: u32,
+}
+
+/// Built-in score of a candidate, see `ScoredCandidate::score`. For composing a `CandidateScorer`.
+pub fn default_candidate_score(candidate: &ScoredCandidate) -> i64 {
+    candidate.score as i64
+}
+
+/// Decides how exporting handles a file extension which does not match the image format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtensionCheck {
+    /// Writes the file without any checks.
+    #[default]
+    Ignore,
+    /// Does not write the file and returns `FavilibError::FormatMismatchError`.
+    Deny,
+}
+
+/// Result of `Favicon::fetch_best_effort`.
+#[derive(Debug, Clone)]
+pub struct BestEffortFavicon {
+    pub favicon: Favicon,
+
+    /// Whether the favicon meets `min_dimensions` and `reject_placeholders`. Favicons which don't
+    /// are returned only because no candidate met them, e.g. to flag the site for a later re-crawl.
+    pub quality_ok: bool,
+}
+
+/// Describes how `Favicon::resize_with_report` transformed the image.
+/// Resizing scales the image uniformly until it covers the new dimensions and crops the overflow around the center.
+/// It never pads the image, use `Favicon::pad_to_square` for that.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResizeReport {
+    pub original_dimensions: (u32, u32),
+    pub dimensions: (u32, u32),
+
+    /// Factor the width was scaled by before cropping.
+    pub scale_x: f64,
+
+    /// Factor the height was scaled by before cropping.
+    pub scale_y: f64,
+
+    /// Whether parts of the scaled image were cropped because its aspect ratio differs from the new one.
+    pub cropped: bool,
+
+    /// Position of the new image within the scaled image. `(0, 0)` if nothing was cropped.
+    pub crop_offset: (u32, u32),
+}
+
+impl ResizeReport {
+    /// Reproduces the scaling of `DynamicImage::resize_to_fill`, which is used by `Favicon::resize`.
+    fn new(original_dimensions: (u32, u32), dimensions: (u32, u32)) -> Self {
+        let (original_width, original_height) = original_dimensions;
+        let (width, height) = dimensions;
+
+        let (scaled_width, scaled_height) = if original_dimensions == dimensions {
+            dimensions
+        } else {
+            let ratio = (width as f64 / original_width.max(1) as f64)
+                .max(height as f64 / original_height.max(1) as f64);
+            let scaled = |value: u32| ((value as f64 * ratio).round() as u32).max(1);
+            (scaled(original_width), scaled(original_height))
+        };
+
+        Self {
+            original_dimensions,
+            dimensions,
+            scale_x: scaled_width as f64 / original_width.max(1) as f64,
+            scale_y: scaled_height as f64 / original_height.max(1) as f64,
+            cropped: (scaled_width, scaled_height) != dimensions,
+            crop_offset: (
+                scaled_width.saturating_sub(width) / 2,
+                scaled_height.saturating_sub(height) / 2,
+            ),
+        }
+    }
+
+    /// Maps a position in the resized image back to the original image.
+    pub fn to_original(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x + self.crop_offset.0 as f64) / self.scale_x,
+            (y + self.crop_offset.1 as f64) / self.scale_y,
+        )
+    }
+}
+
+/// Sizes of the images embedded in the `favicon.ico` written by `Favicon::save_ico_favicon_set`.
+pub const ICO_FAVICON_SET_SIZES: [u32; 3] = [16, 32, 48];
+
+/// Largest width or height a favicon can be resized to.
+pub const MAX_DIMENSION: u32 = 4096;
+
+/// Largest number of frames decoded from an animated favicon.
+pub const MAX_ANIMATION_FRAMES: usize = 1000;
+
+/// Represents the size of the image to be fetched.
+/// Default values are: Small (16x16), Medium (32x32), Large (64x64).
+/// Custom allows for custom sizes to be set.
+/// Default uses the original size of the image.
+/// Sizes are ordered by their pixel area, see `sort_key`.
+/// New sizes may be added in minor releases, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImageSize {
+    Small,
+    Medium,
+    Large,
+    Custom(u32, u32),
+    Default,
+    Invalid,
+}
+
+impl ImageSize {
+    /// Checks that the size can be used for resizing.
+    /// Custom sizes need non-zero dimensions of at most `MAX_DIMENSION`.
+    pub fn validate(&self) -> Result<(), FavilibError> {
+        match *self {
+            ImageSize::Invalid | ImageSize::Custom(0, _) | ImageSize::Custom(_, 0) => {
+                Err(FavilibError::SizeParseError)
+            }
+            ImageSize::Custom(width, height) if width.max(height) > MAX_DIMENSION => {
+                Err(FavilibError::SizeTooLargeError(MAX_DIMENSION))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Caps custom sizes to the given maxima while preserving their aspect ratio.
+    /// Keyword sizes and custom sizes which already fit are returned unchanged.
+    pub fn clamp(self, max_width: u32, max_height: u32) -> ImageSize {
+        match self {
+            ImageSize::Custom(width, height)
+                if width > 0 && height > 0 && (width > max_width || height > max_height) =>
+            {
+                let scale =
+                    (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+                let scaled = |value: u32| ((value as f64 * scale).round() as u32).max(1);
+                ImageSize::Custom(
+                    scaled(width).min(max_width.max(1)),
+                    scaled(height).min(max_height.max(1)),
+                )
+            }
+            size => size,
+        }
+    }
+
+    /// Width and height of the size. Returns `None` for `Default` and `Invalid`.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        match *self {
+            ImageSize::Small => Some((16, 16)),
+            ImageSize::Medium => Some((32, 32)),
+            ImageSize::Large => Some((64, 64)),
+            ImageSize::Custom(width, height) => Some((width, height)),
+            ImageSize::Default | ImageSize::Invalid => None,
+        }
+    }
+
+    /// Key the sizes are ordered by: pixel area first, then width and height.
+    /// `Default` and `Invalid` have no dimensions and sort after all other sizes, in this order.
+    /// Keyword sizes sort before custom sizes with the same dimensions, e.g. `Small < Custom(16, 16)`.
+    pub fn sort_key(&self) -> (bool, u64, u32, u32, u8) {
+        let rank = match self {
+            ImageSize::Small | ImageSize::Medium | ImageSize::Large => 0,
+            ImageSize::Custom(..) => 1,
+            ImageSize::Default => 2,
+            ImageSize::Invalid => 3,
+        };
+        match self.dimensions() {
+            Some((width, height)) => (false, width as u64 * height as u64, width, height, rank),
+            None => (true, 0, 0, 0, rank),
+        }
+    }
+}
+
+impl PartialOrd for ImageSize {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ImageSize {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl From<&str> for ImageSize {
+    fn from(s: &str) -> Self {
+        match s {
+            "small" => ImageSize::Small,
+            "medium" => ImageSize::Medium,
+            "large" => ImageSize::Large,
+            "default" => ImageSize::Default,
+            _ => {
+                let parts: Vec<&str> = s.split([',', 'x']).collect();
+                if parts.len() != 2 {
+                    return ImageSize::Invalid;
+                }
+                let width = parts[0].parse();
+                let height = parts[1].parse();
+
+                if width.is_err() || height.is_err() {
+                    return ImageSize::Invalid;
+                }
+
+                ImageSize::Custom(width.unwrap(), height.unwrap())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    #[test]
+    fn test_resize_to_multiple_keeps_order() {
+        let favicon = test_utils::fixture_favicon(DynamicImage::new_rgba8(128, 128));
+        let sizes = [
+            ImageSize::Large,
+            ImageSize::Small,
+            ImageSize::Custom(20, 10),
+        ];
+
+        let resized = favicon.resize_to_multiple(&sizes);
+
+        let dimensions: Vec<_> = resized.iter().map(|f| f.image().dimensions()).collect();
+        assert_eq!(dimensions, vec![(64, 64), (16, 16), (20, 10)]);
+    }
+
+    #[test]
+    fn test_into_parts_round_trip() {
+        let original = test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4));
+
+        let (url, bytes, image) = original.clone().into_parts();
+        let rebuilt = Favicon::from_parts(url, bytes, image);
+
+        assert_eq!(rebuilt.url(), original.url());
+        assert_eq!(rebuilt.bytes(), original.bytes());
+        assert_eq!(rebuilt.image(), original.image());
+    }
+
+    #[test]
+    fn test_debug_summarizes_image() {
+        let debug = format!(
+            "{:?}",
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2))
+        );
+
+        assert!(debug.starts_with(r#"Favicon { url: "https://example.com/favicon.png""#));
+        assert!(debug.contains("dimensions: (4, 2)"));
+        assert!(debug.contains("bytes: 32"));
+    }
+
+    #[test]
+    fn test_display() -> Result<()> {
+        let bytes = test_utils::png_bytes(32, 16);
+        let len = bytes.len();
+        let favicon = Favicon::build(Url::parse("https://example.com/favicon.png")?, bytes)?;
+
+        assert_eq!(
+            favicon.to_string(),
+            format!(
+                "favicon<32x16 png> from https://example.com/favicon.png ({} bytes)",
+                len
+            )
+        );
+        assert_eq!(
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2)).to_string(),
+            "favicon<4x2 raw> from https://example.com/favicon.png (32 bytes)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity() {
+        let black = test_utils::fixture_favicon(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            8,
+            8,
+            Rgba([0, 0, 0, 255]),
+        )));
+        let white = test_utils::fixture_favicon(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            32,
+            32,
+            Rgba([255, 255, 255, 255]),
+        )));
+        let gray = test_utils::fixture_favicon(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            8,
+            8,
+            Rgba([128, 128, 128, 255]),
+        )));
+
+        assert_eq!(black.similarity(&black.resized(ImageSize::Large)), 1.0);
+        assert!(black.similarity(&white) < 0.3);
+        assert_eq!(black.similarity(&gray), gray.similarity(&black));
+        assert!(black.similarity(&gray) > black.similarity(&white));
+    }
+
+    #[test]
+    fn test_try_resize_validates_custom_sizes() {
+        assert!(matches!(
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(8, 8))
+                .try_resize(ImageSize::Custom(0, 0)),
+            Err(FavilibError::SizeParseError)
+        ));
+        assert!(matches!(
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(8, 8))
+                .try_resize(ImageSize::Custom(100_000, 100_000)),
+            Err(FavilibError::SizeTooLargeError(MAX_DIMENSION))
+        ));
+
+        let resized = test_utils::fixture_favicon(DynamicImage::new_rgba8(8, 8))
+            .try_resize(ImageSize::Custom(1, 1))
+            .unwrap();
+        assert_eq!(resized.image().dimensions(), (1, 1));
+    }
+
+    #[test]
+    fn test_resize_with_report() {
+        let (resized, report) = test_utils::fixture_favicon(DynamicImage::new_rgba8(200, 100))
+            .resize_with_report(ImageSize::Custom(50, 50));
+
+        assert_eq!(resized.image().dimensions(), (50, 50));
+        assert_eq!(report.original_dimensions, (200, 100));
+        assert_eq!(report.dimensions, (50, 50));
+        assert_eq!((report.scale_x, report.scale_y), (0.5, 0.5));
+        assert!(report.cropped);
+        assert_eq!(report.crop_offset, (25, 0));
+        assert_eq!(report.to_original(0.0, 50.0), (50.0, 100.0));
+
+        let (_, report) = test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16))
+            .resize_with_report(ImageSize::Large);
+        assert_eq!((report.scale_x, report.scale_y), (4.0, 4.0));
+        assert!(!report.cropped);
+
+        let (_, report) = test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16))
+            .resize_with_report(ImageSize::Default);
+        assert_eq!((report.scale_x, report.scale_y), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_resize_fills_and_crops_center() {
+        let favicon = test_utils::fixture_favicon(test_utils::gradient_image(64, 32));
+
+        let resized = favicon.resized(ImageSize::Custom(16, 16));
+        assert_eq!(resized.image().dimensions(), (16, 16));
+        // The left and right quarters of the gradient are cropped.
+        let red = |x| resized.image().get_pixel(x, 8)[0];
+        assert!(red(0) > 48 && red(0) < 80);
+        assert!(red(15) > 176 && red(15) < 208);
+
+        let upscaled = favicon.ensure_min_size(64);
+        assert_eq!(upscaled.image().dimensions(), (128, 64));
+    }
+
+    #[test]
+    fn test_change_format_round_trips() -> Result<()> {
+        let color = Rgba([30, 144, 255, 255]);
+        let favicon = test_utils::fixture_favicon(test_utils::solid_image(16, 8, color));
+
+        for format in [
+            ImageFormat::Png,
+            ImageFormat::WebP,
+            ImageFormat::Ico,
+            ImageFormat::Gif,
+        ] {
+            let converted = favicon.change_format(format)?;
+            assert_eq!(converted.image().dimensions(), (16, 8), "{:?}", format);
+            assert_eq!(converted.image().get_pixel(4, 4), color, "{:?}", format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_change_format_to_jpeg_drops_transparency() -> Result<()> {
+        let color = Rgba([255, 255, 255, 255]);
+        let favicon = test_utils::fixture_favicon(test_utils::bordered_image(16, 16, 4, color));
+        assert_eq!(favicon.transparency_ratio(), 0.75);
+
+        let converted = favicon.change_format(ImageFormat::Jpeg)?;
+        assert!(!converted.image().color().has_alpha());
+        assert_eq!(converted.transparency_ratio(), 0.0);
+        assert_eq!(converted.image().dimensions(), (16, 16));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resize_ignores_zero_custom_sizes() {
+        let resized = test_utils::fixture_favicon(DynamicImage::new_rgba8(8, 8))
+            .resize(ImageSize::Custom(0, 0));
+
+        assert_eq!(resized.image().dimensions(), (8, 8));
+    }
+
+    #[test]
+    fn test_resize_default_keeps_original_bytes() -> Result<()> {
+        let bytes = test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2)).as_png_bytes()?;
+        let original = Favicon::build(
+            Url::parse("https://example.com/favicon.png")?,
+            bytes.clone(),
+        )?;
+
+        assert_eq!(
+            original.resized(ImageSize::Default).bytes(),
+            bytes.as_slice()
+        );
+        let resized = original.resize(ImageSize::Default);
+        assert_eq!(resized.bytes(), bytes.as_slice());
+        assert_eq!(resized.format_of_bytes(), Some(ImageFormat::Png));
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_pixels_have_no_format() -> Result<()> {
+        let mut pixels = b"\x89PNG\r\n\x1a\n".to_vec();
+        pixels.resize(4 * 4 * 4, 0);
+        let favicon = test_utils::fixture_favicon(DynamicImage::ImageRgba8(
+            RgbaImage::from_raw(4, 4, pixels).unwrap(),
+        ));
+        assert_eq!(image::guess_format(favicon.bytes())?, ImageFormat::Png);
+        assert_eq!(favicon.format_of_bytes(), None);
+
+        let bytes = favicon.as_png_bytes()?;
+        let resized = Favicon::build(Url::parse("https://example.com/favicon.png")?, bytes)?
+            .resize(ImageSize::Custom(2, 2));
+        assert_eq!(resized.format_of_bytes(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_sprite_sheet() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let favicons = [
+            test_utils::fixture_favicon(test_utils::solid_image(32, 32, red)),
+            test_utils::fixture_favicon(test_utils::solid_image(8, 8, blue)),
+            test_utils::fixture_favicon(test_utils::solid_image(16, 16, red)),
+        ];
+
+        let (sheet, positions) = make_sprite_sheet(&favicons, (16, 16), 2).unwrap();
+
+        assert_eq!(sheet.dimensions(), (32, 32));
+        assert_eq!(positions, vec![(0, 0), (16, 0), (0, 16)]);
+        assert_eq!(sheet.get_pixel(8, 8), red);
+        assert_eq!(sheet.get_pixel(24, 8), blue);
+        assert_eq!(sheet.get_pixel(8, 24), red);
+        assert_eq!(sheet.get_pixel(24, 24), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_make_sprite_sheet_rejects_oversized_sheets() {
+        let favicons = [
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4)),
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4)),
+        ];
+
+        for (cell, columns) in [((u32::MAX, 16), 2), ((16, 16), u32::MAX), ((16, 4097), 1)] {
+            assert!(
+                matches!(
+                    make_sprite_sheet(&favicons, cell, columns),
+                    Err(FavilibError::SizeTooLargeError(MAX_DIMENSION))
+                ),
+                "{:?} {}",
+                cell,
+                columns
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_url_keeps_bytes() -> Result<()> {
+        let bytes = test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2)).as_png_bytes()?;
+        let cached =
+            Favicon::build_undecoded(Url::parse("file:///cache/example.png")?, bytes.clone())?;
+
+        let favicon = cached.with_url(Url::parse("https://example.com/favicon.png")?);
+
+        assert_eq!(favicon.url().as_str(), "https://example.com/favicon.png");
+        assert_eq!(favicon.bytes(), bytes.as_slice());
+        assert!(!favicon.is_decoded());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resize_dpr() -> Result<()> {
+        let retina = test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16))
+            .resize_dpr(ImageSize::Medium, 2.0)?;
+        assert_eq!(retina.image().dimensions(), (64, 64));
+
+        let original = test_utils::fixture_favicon(DynamicImage::new_rgba8(20, 10))
+            .resize_dpr(ImageSize::Default, 1.5)?;
+        assert_eq!(original.image().dimensions(), (30, 15));
+
+        for dpr in [0.0, -1.0, f32::NAN] {
+            assert!(matches!(
+                test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16))
+                    .resize_dpr(ImageSize::Small, dpr),
+                Err(FavilibError::DevicePixelRatioError(_))
+            ));
+        }
+        assert!(test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16))
+            .resize_dpr(ImageSize::Invalid, 2.0)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resized_keeps_original() {
+        let original = test_utils::fixture_favicon(DynamicImage::new_rgba8(64, 64));
+
+        let thumbnail = original.resized(ImageSize::Small);
+
+        assert_eq!(thumbnail.image().dimensions(), (16, 16));
+        assert_eq!(original.image().dimensions(), (64, 64));
+    }
+
+    #[test]
+    fn test_rotate90_four_times_is_identity() {
+        let mut image = RgbaImage::new(3, 2);
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        image.put_pixel(2, 1, image::Rgba([0, 0, 255, 255]));
+        let original = test_utils::fixture_favicon(DynamicImage::ImageRgba8(image));
+
+        let rotated = original.rotate90().rotate90().rotate90().rotate90();
+
+        assert_eq!(rotated.image(), original.image());
+        assert_eq!(rotated.bytes(), original.bytes());
+    }
+
+    #[test]
+    fn test_flip_horizontal_mirrors_pixels() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+
+        let flipped =
+            test_utils::fixture_favicon(DynamicImage::ImageRgba8(image)).flip_horizontal();
+
+        assert_eq!(
+            flipped.image().get_pixel(1, 0),
+            image::Rgba([255, 0, 0, 255])
+        );
+        assert_eq!(flipped.image().get_pixel(0, 0), image::Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_pad_to_square_centers_image() {
+        let image = RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255]));
+        let bg = Rgba([0, 0, 255, 255]);
+
+        let padded = test_utils::fixture_favicon(DynamicImage::ImageRgba8(image)).pad_to_square(bg);
+
+        assert_eq!(padded.image().dimensions(), (4, 4));
+        assert_eq!(padded.image().get_pixel(0, 0), bg);
+        assert_eq!(padded.image().get_pixel(0, 1), Rgba([255, 0, 0, 255]));
+        assert_eq!(padded.image().get_pixel(3, 3), bg);
+    }
+
+    #[test]
+    fn test_export_keeps_original_bytes_of_same_format() -> Result<()> {
+        let mut buffer = Cursor::new(Vec::new());
+        test_utils::fixture_favicon(DynamicImage::new_rgba8(8, 8))
+            .image()
+            .write_to(&mut buffer, ImageFormat::Png)?;
+        let bytes = buffer.into_inner();
+        let favicon = Favicon::build(
+            Url::parse("https://example.com/favicon.png")?,
+            bytes.clone(),
+        )?;
+        let path = test_utils::temp_path("original_bytes.png");
+
+        favicon.export(&path, ImageFormat::Png)?;
+
+        assert_eq!(favicon.format_of_bytes(), Some(ImageFormat::Png));
+        assert_eq!(std::fs::read(&path)?, bytes);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_rejects_images_exceeding_limits() -> Result<()> {
+        let mut buffer = Cursor::new(Vec::new());
+        test_utils::fixture_favicon(DynamicImage::new_rgba8(64, 32))
+            .image()
+            .write_to(&mut buffer, ImageFormat::Png)?;
+        let options = DecodeOptions {
+            max_width: 32,
+            ..Default::default()
+        };
+
+        let result = Favicon::build_with_options(
+            Url::parse("https://example.com/favicon.png")?,
+            buffer.into_inner(),
+            &options,
+        );
+
+        assert!(matches!(
+            result,
+            Err(FavilibError::ImageLimitsExceededError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_undecoded() -> Result<()> {
+        let url = Url::parse("https://example.com/favicon.png")?;
+        let bytes = test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2)).as_png_bytes()?;
+
+        let favicon = Favicon::build_undecoded(url.clone(), bytes.clone())?;
+        assert!(!favicon.is_decoded());
+        assert_eq!(favicon.bytes(), bytes.as_slice());
+        assert_eq!(favicon.image().dimensions(), (4, 2));
+        assert!(favicon.is_decoded());
+
+        assert!(Favicon::build_undecoded(url, b"<html></html>".to_vec()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_try_image_reports_decode_errors() -> Result<()> {
+        let url = Url::parse("https://example.com/favicon.png")?;
+        let options = DecodeOptions {
+            max_width: 4,
+            ..Default::default()
+        };
+
+        let bytes = test_utils::png_bytes(8, 8);
+        let large = Favicon::build_undecoded_with_options(url.clone(), bytes.clone(), &options)?;
+        assert!(matches!(
+            large.try_image(),
+            Err(FavilibError::ImageLimitsExceededError)
+        ));
+        assert_eq!(large.image().dimensions(), (0, 0));
+        assert!(!large.is_decoded());
+
+        let broken = Favicon::build_undecoded(url, bytes[..40].to_vec())?;
+        assert!(broken.try_image().is_err());
+        assert!(broken.clone().resize_dpr(ImageSize::Default, 2.0).is_err());
+        assert!(broken.clone().resize_dpr(ImageSize::Small, 2.0).is_err());
+        assert!(broken.clone().try_resize(ImageSize::Small).is_err());
+        assert!(make_sprite_sheet(std::slice::from_ref(&broken), (16, 16), 1).is_err());
+        assert_eq!(broken.resized(ImageSize::Small).bytes(), &bytes[..40]);
+        let (resized, report) = broken.clone().resize_with_report(ImageSize::Small);
+        assert_eq!(resized.bytes(), &bytes[..40]);
+        assert_eq!(report.dimensions, (0, 0));
+        assert_eq!(
+            format!("{}", broken),
+            format!("favicon<?x? png> from {} (40 bytes)", broken.url())
+        );
+        assert!(format!("{:?}", broken).contains("dimensions: undecoded"));
+        assert!(!broken.is_decoded());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rank_favicon_candidates() -> Result<()> {
+        let html = r#"<head>
+            <link rel="apple-touch-icon" sizes="180x180" href="/apple-touch-icon.png">
+            <link rel="icon" href="/favicon.svg" sizes="any">
+        </head>"#;
+
+        let candidates = rank_favicon_candidates(
+            html,
+            Url::parse("https://example.com")?,
+            &FetchOptions::default(),
+        );
+
+        assert_eq!(
+            candidates,
+            vec![
+                ScoredCandidate {
+                    url: Url::parse("https://example.com/apple-touch-icon.png")?,
+                    rel: Some("apple-touch-icon".to_string()),
+                    sizes: Some("180x180".to_string()),
+                    score: 3,
+                },
+                ScoredCandidate {
+                    url: Url::parse("https://example.com/favicon.svg")?,
+                    rel: Some("icon".to_string()),
+                    sizes: Some("any".to_string()),
+                    score: 2,
+                },
+                ScoredCandidate {
+                    url: Url::parse("https://example.com/favicon.ico")?,
+                    rel: None,
+                    sizes: None,
+                    score: 1,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_favicon_urls() -> Result<()> {
+        let html = r#"<html><head><link rel="icon" href="icons/favicon.svg"></head></html>"#;
+
+        let urls = discover_favicon_urls(html, Url::parse("https://example.com/blog/")?);
+
+        assert_eq!(
+            urls,
+            vec![
+                Url::parse("https://example.com/blog/icons/favicon.svg")?,
+                Url::parse("https://example.com/favicon.ico")?,
+            ]
+        );
+        Ok(())
+    }
+
+    /// Encodes a 2x1 PNG whose EXIF data asks for a 90 degree rotation.
+    fn rotated_png() -> Result<Vec<u8>> {
+        let exif = vec![
+            b'M', b'M', 0, 42, 0, 0, 0, 8, // TIFF header
+            0, 1, // one IFD entry
+            0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, // orientation: rotate 90
+            0, 0, 0, 0, // no further IFD
+        ];
+        let mut buffer = Vec::new();
+        let mut encoder = image::codecs::png::PngEncoder::new(&mut buffer);
+        image::ImageEncoder::set_exif_metadata(&mut encoder, exif)?;
+        image::ImageEncoder::write_image(encoder, &[0; 8], 2, 1, image::ExtendedColorType::Rgba8)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn test_build_applies_exif_orientation() -> Result<()> {
+        let url = Url::parse("https://example.com/favicon.png")?;
+
+        let favicon = Favicon::build(url.clone(), rotated_png()?)?;
+        assert_eq!(favicon.image().dimensions(), (1, 2));
+
+        let options = DecodeOptions {
+            apply_orientation: false,
+            ..Default::default()
+        };
+        let favicon = Favicon::build_with_options(url, rotated_png()?, &options)?;
+        assert_eq!(favicon.image().dimensions(), (2, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_denies_mismatching_extension() {
+        let favicon = test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16));
+        let path = test_utils::temp_path("mismatch.ico");
+
+        let result = favicon.export_with_check(&path, ImageFormat::Png, ExtensionCheck::Deny);
+
+        assert!(matches!(
+            result,
+            Err(FavilibError::FormatMismatchError(ImageFormat::Png))
+        ));
+        assert!(!path.exists());
+
+        favicon.export(&path, ImageFormat::Png).unwrap();
+        assert_eq!(
+            image::guess_format(&std::fs::read(&path).unwrap()).unwrap(),
+            ImageFormat::Png
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_select_ico_entry() -> Result<()> {
+        let mut dir = ico::IconDir::new(ico::ResourceType::Icon);
+        for size in [16, 32] {
+            let image =
+                ico::IconImage::from_rgba_data(size, size, vec![0; (size * size * 4) as usize]);
+            dir.add_entry(ico::IconDirEntry::encode(&image)?);
+        }
+        let mut bytes = Vec::new();
+        dir.write(&mut bytes)?;
+
+        let favicon = Favicon::build(Url::parse("https://example.com/favicon.ico")?, bytes)?;
+        assert_eq!(favicon.ico_entries(), vec![(16, 16), (32, 32)]);
+        assert_eq!(
+            favicon.select_ico_entry(16, 16)?.image().dimensions(),
+            (16, 16)
+        );
+        assert!(matches!(
+            favicon.select_ico_entry(48, 48),
+            Err(FavilibError::IcoEntryNotFoundError(48, 48))
+        ));
+        assert!(test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16))
+            .ico_entries()
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resize_uses_matching_ico_entry() -> Result<()> {
+        let mut dir = ico::IconDir::new(ico::ResourceType::Icon);
+        for (size, value) in [(16, 10), (64, 200)] {
+            let image =
+                ico::IconImage::from_rgba_data(size, size, vec![value; (size * size * 4) as usize]);
+            dir.add_entry(ico::IconDirEntry::encode(&image)?);
+        }
+        let mut bytes = Vec::new();
+        dir.write(&mut bytes)?;
+        let favicon = Favicon::build(Url::parse("https://example.com/favicon.ico")?, bytes)?;
+
+        let small = favicon.resized(ImageSize::Small);
+        assert_eq!(small.image().dimensions(), (16, 16));
+        assert_eq!(small.image().get_pixel(8, 8), Rgba([10, 10, 10, 10]));
+
+        let scaled = favicon.resize(ImageSize::Medium);
+        assert_eq!(scaled.image().dimensions(), (32, 32));
+        assert_eq!(scaled.image().get_pixel(8, 8), Rgba([200, 200, 200, 200]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_ico_favicon_set() -> Result<()> {
+        let path = test_utils::temp_path("ico_favicon_set.ico");
+
+        test_utils::fixture_favicon(DynamicImage::new_rgba8(128, 64))
+            .save_ico_favicon_set(&path)?;
+
+        let saved = Favicon::from_path(&path)?;
+        assert_eq!(saved.format_of_bytes(), Some(ImageFormat::Ico));
+        assert_eq!(saved.ico_entries(), vec![(16, 16), (32, 32), (48, 48)]);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_path() -> Result<()> {
+        let path = test_utils::temp_path("from_path.png");
+        std::fs::write(&path, test_utils::png_bytes(8, 4))?;
+
+        let favicon = Favicon::from_path(&path)?;
+        assert_eq!(favicon.image().dimensions(), (8, 4));
+        assert_eq!(favicon.url().scheme(), "file");
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensure_min_size() {
+        let upscaled =
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 8)).ensure_min_size(32);
+        assert_eq!(upscaled.image().dimensions(), (64, 32));
+
+        let unchanged =
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(48, 48)).ensure_min_size(32);
+        assert_eq!(unchanged.image().dimensions(), (48, 48));
+        assert_eq!(
+            unchanged.bytes(),
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(48, 48)).bytes()
+        );
+    }
+
+    #[test]
+    fn test_cap_size() {
+        let downscaled = test_utils::fixture_favicon(DynamicImage::new_rgba8(128, 64)).cap_size(32);
+        assert_eq!(downscaled.image().dimensions(), (32, 16));
+
+        let unchanged = test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16)).cap_size(32);
+        assert_eq!(unchanged.image().dimensions(), (16, 16));
+        assert_eq!(
+            unchanged.bytes(),
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(16, 16)).bytes()
+        );
+    }
+
+    #[test]
+    fn test_change_format_keeps_gif_animation() -> Result<()> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            encoder.encode_frames([
+                Frame::new(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]))),
+                Frame::new(RgbaImage::from_pixel(4, 4, Rgba([0, 0, 255, 255]))),
+            ])?;
+        }
+        let favicon = Favicon::build(Url::parse("https://example.com/favicon.gif")?, bytes)?;
+
+        let animated = favicon.change_format(ImageFormat::Gif)?;
+        let frames = GifDecoder::new(Cursor::new(animated.bytes()))?
+            .into_frames()
+            .collect_frames()?;
+        assert_eq!(frames.len(), 2);
+
+        assert!(favicon
+            .change_format(ImageFormat::Png)?
+            .animation_frames()?
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_animation_frames_respect_limits() -> Result<()> {
+        let url = Url::parse("https://example.com/favicon.gif")?;
+        let animation = |count| -> Result<Vec<u8>> {
+            let mut bytes = Vec::new();
+            {
+                let mut encoder = GifEncoder::new(&mut bytes);
+                let frames = (0..count)
+                    .map(|i| Frame::new(RgbaImage::from_pixel(4, 4, Rgba([i as u8, 0, 0, 255]))));
+                encoder.encode_frames(frames)?;
+            }
+            Ok(bytes)
+        };
+
+        // Each frame takes 64 bytes, so the first one decodes but not all three.
+        let options = DecodeOptions {
+            max_alloc: 100,
+            ..Default::default()
+        };
+        let favicon = Favicon::build_with_options(url.clone(), animation(3)?, &options)?;
+        assert!(matches!(
+            favicon.animation_frames(),
+            Err(FavilibError::ImageLimitsExceededError)
+        ));
+        assert!(favicon.change_format(ImageFormat::Gif).is_err());
+
+        let favicon = Favicon::build(url, animation(MAX_ANIMATION_FRAMES + 1)?)?;
+        assert!(matches!(
+            favicon.animation_frames(),
+            Err(FavilibError::ImageLimitsExceededError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        let url = Url::parse("https://example.com/favicon.png")?;
+        let favicon = Favicon::build(url.clone(), test_utils::png_bytes(4, 4))?;
+        favicon.validate()?;
+        favicon.validate_formats(&[ImageFormat::Png, ImageFormat::Ico])?;
+        assert!(matches!(
+            favicon.validate_formats(&[ImageFormat::Ico]),
+            Err(FavilibError::FormatNotAllowedError(ImageFormat::Png))
+        ));
+
+        let broken = Favicon::from_parts(url.clone(), vec![0; 16], DynamicImage::new_rgba8(4, 4));
+        assert!(matches!(
+            broken.validate(),
+            Err(FavilibError::ImageDecodeError(_))
+        ));
+
+        let options = DecodeOptions {
+            max_width: 2,
+            ..Default::default()
+        };
+        let large =
+            Favicon::build_undecoded_with_options(url, test_utils::png_bytes(4, 4), &options)?;
+        assert!(matches!(
+            large.validate(),
+            Err(FavilibError::ImageLimitsExceededError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_size_clamp() {
+        assert_eq!(
+            ImageSize::Custom(8192, 4096).clamp(1024, 1024),
+            ImageSize::Custom(1024, 512)
+        );
+        assert_eq!(
+            ImageSize::Custom(32, 16).clamp(1024, 1024),
+            ImageSize::Custom(32, 16)
+        );
+        assert_eq!(ImageSize::Large.clamp(16, 16), ImageSize::Large);
+    }
+
+    #[test]
+    fn test_image_size_ordering() {
+        assert!(ImageSize::Custom(100, 100) > ImageSize::Large);
+        assert!(ImageSize::Custom(8, 8) < ImageSize::Small);
+        assert!(ImageSize::Small < ImageSize::Custom(16, 16));
+        assert!(ImageSize::Custom(2, 8) < ImageSize::Custom(4, 4));
+
+        let mut sizes = vec![
+            ImageSize::Invalid,
+            ImageSize::Default,
+            ImageSize::Custom(100, 100),
+            ImageSize::Large,
+            ImageSize::Custom(8, 8),
+            ImageSize::Medium,
+        ];
+        sizes.sort();
+        assert_eq!(
+            sizes,
+            vec![
+                ImageSize::Custom(8, 8),
+                ImageSize::Medium,
+                ImageSize::Large,
+                ImageSize::Custom(100, 100),
+                ImageSize::Default,
+                ImageSize::Invalid,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_reports_error_of_guessed_format() -> Result<()> {
+        let url = Url::parse("https://example.com/favicon.jpg")?;
+        let bytes = vec![0xff, 0xd8, 0xff, 0xe0, 0, 0, 0, 0];
+
+        match Favicon::build(url.clone(), bytes) {
+            Err(FavilibError::ImageDecodeError(ImageError::Decoding(e))) => assert_eq!(
+                e.format_hint(),
+                image::error::ImageFormatHint::Exact(ImageFormat::Jpeg)
+            ),
+            _ => panic!("Expected the JPEG decoding error"),
+        }
+
+        let favicon = Favicon::build(url, test_utils::png_bytes(4, 4))?;
+        assert_eq!(favicon.image().dimensions(), (4, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transparency_ratio() {
+        let mut image = RgbaImage::new(2, 2);
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        assert_eq!(
+            test_utils::fixture_favicon(DynamicImage::ImageRgba8(image)).transparency_ratio(),
+            0.75
+        );
+        assert_eq!(
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4)).transparency_ratio(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_has_transparency_and_recommended_format() {
+        let white = Rgba([255, 255, 255, 255]);
+        let transparent = test_utils::fixture_favicon(test_utils::bordered_image(8, 8, 2, white));
+        let flat = test_utils::fixture_favicon(test_utils::solid_image(8, 8, white));
+        let photo = test_utils::fixture_favicon(DynamicImage::ImageRgba8(RgbaImage::from_fn(
+            32,
+            32,
+            |x, y| Rgba([(x * 8) as u8, (y * 8) as u8, 128, 255]),
+        )));
+
+        assert!(transparent.has_transparency());
+        assert!(!flat.has_transparency());
+        assert!(!photo.has_transparency());
+
+        assert_eq!(transparent.recommended_format(), ImageFormat::Png);
+        assert_eq!(flat.recommended_format(), ImageFormat::Png);
+        assert_eq!(photo.recommended_format(), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn test_to_ansi_preview() {
+        let red = Rgba([255, 0, 0, 255]);
+        let preview =
+            test_utils::fixture_favicon(test_utils::solid_image(8, 4, red)).to_ansi_preview(4);
+
+        assert_eq!(
+            preview,
+            "\x1b[38;2;255;0;0m\x1b[48;2;255;0;0m\u{2580}".repeat(4) + "\x1b[0m\n"
+        );
+
+        let transparent =
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4)).to_ansi_preview(2);
+        assert_eq!(transparent, "\x1b[0m \x1b[0m \x1b[0m\n");
+        assert_eq!(
+            test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4)).to_ansi_preview(0),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_to_rgba8_and_rgb8() {
+        let image = RgbaImage::from_pixel(3, 2, Rgba([10, 20, 30, 40]));
+        let favicon = test_utils::fixture_favicon(DynamicImage::ImageRgba8(image.clone()));
+
+        assert_eq!(favicon.to_rgba8(), image);
+        assert_eq!(favicon.to_rgb8().dimensions(), (3, 2));
+        assert_eq!(favicon.to_rgb8().get_pixel(0, 0), &image::Rgb([10, 20, 30]));
+    }
+
+    #[test]
+    fn test_html_and_css_snippets() -> Result<()> {
+        let favicon = Favicon::build(
+            Url::parse("https://example.com/favicon.png")?,
+            test_utils::png_bytes(1, 1),
+        )?;
+        let data_uri = format!(
+            "data:image/png;base64,{}",
+            BASE64_STANDARD.encode(test_utils::png_bytes(1, 1))
+        );
+
+        assert_eq!(
+            favicon.to_html_link(ImageFormat::Png)?,
+            format!(r#"<link rel="icon" type="image/png" href="{}">"#, data_uri)
+        );
+        assert_eq!(
+            favicon.to_css_background(".logo", ImageFormat::Png)?,
+            format!(r#".logo {{ background-image: url("{}"); }}"#, data_uri)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_as_png_and_webp_bytes() -> Result<()> {
+        let favicon = test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2));
+
+        let png = favicon.as_png_bytes()?;
+        assert_eq!(image::guess_format(&png)?, ImageFormat::Png);
+        let webp = favicon.as_webp_bytes()?;
+        assert_eq!(image::guess_format(&webp)?, ImageFormat::WebP);
+
+        let built = Favicon::build(favicon.url().clone(), png.clone())?;
+        assert_eq!(built.as_png_bytes()?, png);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_formats() -> Result<()> {
+        let favicon = test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 2));
+
+        let encoded = favicon.to_formats(&[ImageFormat::Png, ImageFormat::WebP])?;
+        assert_eq!(encoded.len(), 2);
+        for (format, bytes) in encoded {
+            assert_eq!(image::guess_format(&bytes)?, format);
+        }
+
+        let error = favicon
+            .to_formats(&[ImageFormat::Png, ImageFormat::Tiff, ImageFormat::Hdr])
+            .unwrap_err();
+        assert!(
+            matches!(error, FavilibError::OtherError(e) if e.to_string() == "Can't encode image as Hdr")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_size() -> Result<()> {
+        let favicon = test_utils::fixture_favicon(DynamicImage::new_rgba8(8, 8));
+        let path = test_utils::temp_path("encoded_size.webp");
+
+        favicon.export(&path, ImageFormat::WebP)?;
+
+        assert_eq!(
+            favicon.encoded_size(ImageFormat::WebP)?,
+            std::fs::metadata(&path)?.len() as usize
+        );
+        assert_eq!(
+            favicon.encoded_size(ImageFormat::Png)?,
+            favicon.as_png_bytes()?.len()
+        );
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_create_dirs() -> Result<()> {
+        let dir = test_utils::temp_path("export_create_dirs");
+        let path = dir.join("site").join("favicon.png");
+
+        assert!(test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4))
+            .export(&path, ImageFormat::Png)
+            .is_err());
+        test_utils::fixture_favicon(DynamicImage::new_rgba8(4, 4))
+            .export_create_dirs(&path, ImageFormat::Png)?;
+        assert!(path.exists());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
     }
 }
@@ -0,0 +1,303 @@
+use image::ImageFormat;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+use super::ScoredCandidate;
+
+/// `rel` values of the links recognized as icons by default.
+/// Meta tags whose content contains one of the values are recognized as well.
+pub const DEFAULT_ICON_RELS: &[&str] = &[
+    "icon",
+    "alternate icon",
+    "shortcut icon",
+    "apple-touch-icon",
+    "apple-touch-icon-precomposed",
+    "favicon",
+    "mask-icon",
+    "fluid-icon",
+    "image",
+];
+
+/// `rel` values of links which are never recognized as icons, even if they match one of the icon rels.
+pub const EXCLUDED_ICON_RELS: &[&str] = &["apple-touch-startup-image"];
+
+/// Maximum number of threads fetching the candidates of a page if `FetchOptions::max_fetch_threads` is not set.
+pub const DEFAULT_MAX_FETCH_THREADS: usize = 4;
+
+/// Options controlling how favicons are discovered and fetched.
+/// The default values match the behaviour of `Favicon::fetch`.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// Scans the whole HTML document for icon links instead of only the `<head>` section.
+    /// Links inside `<noscript>` fallbacks are included as well.
+    /// The web app manifests linked via `<link rel="manifest">` are fetched and their `icons` are tried after the
+    /// other candidates. `discover_favicon_urls` and `rank_favicon_candidates` don't fetch anything, so they skip them.
+    /// Useful for single-page apps which do not declare their icons in the served `<head>`.
+    pub scan_whole_document: bool,
+
+    /// Fetches the favicon from the page linked via `<link rel="canonical">` if it differs from the requested page.
+    /// Only one hop is followed. Falls back to the requested page if the canonical page yields no favicon.
+    pub follow_canonical: bool,
+
+    /// Fetches the favicon from the target of a `<meta http-equiv="refresh">` redirect on the requested page.
+    /// Only refreshes without delay are followed, for one hop. Falls back to the requested page if the target
+    /// yields no favicon.
+    pub follow_meta_refresh: bool,
+
+    /// Maximum number of idle connections kept per host by the default client.
+    /// `None` keeps the reqwest default. Ignored if a custom client is passed.
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Enables the HTTP/2 adaptive flow control window on the default client.
+    /// Ignored if a custom client is passed.
+    pub http2_adaptive_window: bool,
+
+    /// TCP keep-alive interval used by the default client.
+    /// `None` keeps the reqwest default. Ignored if a custom client is passed.
+    pub tcp_keepalive: Option<Duration>,
+
+    /// Makes the default client accept invalid TLS certificates, e.g. self-signed or expired ones.
+    /// This makes the connection vulnerable to man-in-the-middle attacks. Ignored if a custom client is passed.
+    pub accept_invalid_certs: bool,
+
+    /// Retries an `https` URL over plain `http` if the page can't be requested, e.g. because of a TLS error.
+    /// The response is not protected against tampering. `Favicon::page_url` has the `http` scheme if the
+    /// retry was used.
+    pub allow_http_fallback: bool,
+
+    /// Disables retrying the same URL with `www.` added or stripped if the page yields no favicon.
+    pub disable_www_fallback: bool,
+
+    /// Hosts which are never retried with `www.` added or stripped, e.g. apex domains without a `www.` subdomain.
+    /// Hosts are compared case-insensitively. Other hosts are still retried unless `disable_www_fallback` is set.
+    pub www_fallback_excluded_hosts: Vec<String>,
+
+    /// URL used to resolve relative links instead of the URL the page is fetched from.
+    /// Useful for mock servers, proxies and caches which serve a site under a different URL.
+    /// `None` resolves links against the fetched page.
+    pub resolve_base: Option<Url>,
+
+    /// Formats a candidate has to be in to be selected. Other formats are skipped before decoding.
+    /// `None` or an empty list accepts all formats.
+    pub accepted_formats: Option<Vec<ImageFormat>>,
+
+    /// Adds the largest image of `<link rel="preload" as="image" imagesrcset="...">` links as candidates.
+    /// These are often logos rather than favicons, so they are appended after all other candidates.
+    pub preload_image_candidates: bool,
+
+    /// Falls back to the largest `<img>` in the `<head>`, `<header>` or `<nav>` of the page if no candidate decodes.
+    /// Images are ranked by their declared `width` and `height`. Images declared smaller than 16 pixels are skipped.
+    pub logo_fallback: bool,
+
+    /// Skips placeholder images which some servers return instead of a 404, and continues with the next candidate.
+    /// Images which are 2 pixels or smaller in either dimension and fully transparent images are skipped.
+    pub reject_placeholders: bool,
+
+    /// Minimum width and height of a candidate. Smaller candidates are skipped after decoding,
+    /// e.g. to prefer a 180x180 touch icon over a 16x16 `favicon.ico`. `None` accepts all sizes.
+    pub min_dimensions: Option<(u32, u32)>,
+
+    /// `rel` values of the links recognized as icons. A link matches if its `rel` contains all space-separated
+    /// tokens of one of the values, compared case-insensitively. Links with a rel in `EXCLUDED_ICON_RELS` never match.
+    /// `None` uses `DEFAULT_ICON_RELS`. `/favicon.ico` is always tried, even for an empty list.
+    pub icon_rels: Option<Vec<String>>,
+
+    /// Fetches the candidates one after another in the order they are declared and selects the first which decodes.
+    /// Makes the selection reproducible at the cost of speed. By default the candidates are fetched in parallel
+    /// and the first one to finish wins.
+    pub deterministic: bool,
+
+    /// Maximum number of threads fetching the candidates of a page in parallel. Values below 1 are treated as 1.
+    /// `None` uses `DEFAULT_MAX_FETCH_THREADS`. Independent of the concurrency of `fetch_many`.
+    pub max_fetch_threads: Option<usize>,
+
+    /// Query parameters added to every favicon request, e.g. `("v", "2")` to bypass stale CDN caches.
+    /// The existing query of a candidate URL is kept, parameters with the same name are replaced.
+    pub icon_query: Vec<(String, String)>,
+
+    /// `Accept` header sent with the favicon requests, e.g. `image/webp,image/png,*/*`.
+    /// Lets servers which negotiate the format serve a preferred one. `None` sends the client's default.
+    pub icon_accept: Option<String>,
+
+    /// `Accept-Language` header sent with the page requests, e.g. `en-US,en;q=0.9`.
+    /// Pins the markup of sites which localize their pages. Not sent with the favicon requests.
+    pub accept_language: Option<String>,
+
+    /// Logical host of the requested URL, e.g. `staging.example.com`, sent as its `Host` header.
+    /// Lets a URL address a backend by IP while the load balancer routes by the logical host, e.g. for staging.
+    /// Links resolve against the logical host, and requests for it are sent to the requested URL with the header.
+    /// Favicons on other hosts, e.g. a CDN, are requested as usual without it.
+    pub host_override: Option<String>,
+
+    /// Third-party favicon service which is asked if no favicon is found on the website itself.
+    /// `None` never contacts a third party.
+    pub favicon_service: Option<FaviconService>,
+
+    /// Verifies candidates against the `integrity` attribute of their link, see Subresource Integrity.
+    /// Candidates whose bytes match none of the hashes of the strongest listed algorithm are rejected.
+    /// Links without the attribute or with only unsupported algorithms are accepted. Supports SHA-256, -384 and -512.
+    /// Needs the `integrity` feature.
+    #[cfg(feature = "integrity")]
+    pub verify_integrity: bool,
+
+    /// Accepts candidates by their magic bytes instead of decoding them, for archiving favicon bytes.
+    /// The image is decoded on the first call of `Favicon::image` or `Favicon::try_image`, with `decode_options`.
+    /// `reject_placeholders` and `min_dimensions` still decode each candidate they check.
+    pub skip_decode: bool,
+
+    /// Replaces the built-in ranking of the candidates of a page. The candidate with the highest score is tried
+    /// first, ties keep the built-in order. Candidates are fetched one after another, like with `deterministic`.
+    /// Use `default_candidate_score` to adjust the built-in score instead of replacing it.
+    pub candidate_scorer: Option<CandidateScorer>,
+
+    /// Options used to decode the fetched candidates.
+    pub decode_options: DecodeOptions,
+}
+
+/// Third-party favicon service, e.g. the ones of Google or DuckDuckGo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaviconService {
+    /// URL of the favicon. `{host}` is replaced with the host of the website and `{size}` with `size`.
+    pub template: String,
+
+    /// Requested size of the favicon in pixels. Ignored by services without a size parameter.
+    pub size: u32,
+}
+
+impl FaviconService {
+    /// Favicon service of Google.
+    pub fn google(size: u32) -> Self {
+        Self {
+            template: "https://www.google.com/s2/favicons?domain={host}&sz={size}".to_string(),
+            size,
+        }
+    }
+
+    /// Favicon service of DuckDuckGo. Serves the favicon in its original size.
+    pub fn duckduckgo() -> Self {
+        Self {
+            template: "https://icons.duckduckgo.com/ip3/{host}.ico".to_string(),
+            size: 0,
+        }
+    }
+
+    /// Fills in the template for the given website. Returns `None` if it has no host.
+    pub(crate) fn url_for(&self, url: &Url) -> Option<Url> {
+        let service_url = self
+            .template
+            .replace("{host}", url.host_str()?)
+            .replace("{size}", &self.size.to_string());
+        Url::parse(&service_url).ok()
+    }
+}
+
+impl FetchOptions {
+    /// Whether the URL may be retried with `www.` added or stripped.
+    pub(crate) fn allows_www_fallback(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default();
+        !self.disable_www_fallback
+            && !self
+                .www_fallback_excluded_hosts
+                .iter()
+                .any(|excluded| excluded.eq_ignore_ascii_case(host))
+    }
+
+    /// Adds `icon_query` to the query of the given candidate URL.
+    pub(crate) fn with_icon_query(&self, mut url: Url) -> Url {
+        if self.icon_query.is_empty() {
+            return url;
+        }
+
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !self.icon_query.iter().any(|(added, _)| added == name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(kept.iter().chain(&self.icon_query));
+        url
+    }
+
+    /// Number of threads used to fetch the given number of candidates.
+    pub(crate) fn fetch_threads(&self, candidates: usize) -> usize {
+        self.max_fetch_threads
+            .unwrap_or(DEFAULT_MAX_FETCH_THREADS)
+            .clamp(1, candidates.max(1))
+    }
+
+    pub(crate) fn icon_rels(&self) -> Vec<&str> {
+        match &self.icon_rels {
+            Some(rels) => rels.iter().map(String::as_str).collect(),
+            None => DEFAULT_ICON_RELS.to_vec(),
+        }
+    }
+
+    /// Whether candidates are checked against their `integrity` attribute. Always false without the feature.
+    pub(crate) fn verifies_integrity(&self) -> bool {
+        #[cfg(feature = "integrity")]
+        return self.verify_integrity;
+        #[cfg(not(feature = "integrity"))]
+        false
+    }
+}
+
+/// Scores the favicon candidates of a page, e.g. to prefer square or PNG icons. Higher scores are tried first.
+#[derive(Clone)]
+pub struct CandidateScorer(Arc<dyn Fn(&ScoredCandidate) -> i64 + Send + Sync>);
+
+impl CandidateScorer {
+    pub fn new(scorer: impl Fn(&ScoredCandidate) -> i64 + Send + Sync + 'static) -> Self {
+        Self(Arc::new(scorer))
+    }
+
+    pub fn score(&self, candidate: &ScoredCandidate) -> i64 {
+        (self.0)(candidate)
+    }
+}
+
+impl fmt::Debug for CandidateScorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CandidateScorer")
+    }
+}
+
+/// Options controlling how image bytes are decoded.
+/// Fetched images are untrusted, so the defaults guard against decompression bombs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Maximum width of a decoded image in pixels. Defaults to 4096.
+    pub max_width: u32,
+
+    /// Maximum height of a decoded image in pixels. Defaults to 4096.
+    pub max_height: u32,
+
+    /// Maximum number of bytes the decoder may allocate. Defaults to 128 MiB.
+    pub max_alloc: u64,
+
+    /// Rotates and flips the decoded image according to its EXIF orientation. Defaults to true.
+    pub apply_orientation: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 4096,
+            max_height: 4096,
+            max_alloc: 128 * 1024 * 1024,
+            apply_orientation: true,
+        }
+    }
+}
+
+impl DecodeOptions {
+    pub(crate) fn limits(&self) -> image::Limits {
+        let mut limits = image::Limits::default();
+        limits.max_image_width = Some(self.max_width);
+        limits.max_image_height = Some(self.max_height);
+        limits.max_alloc = Some(self.max_alloc);
+        limits
+    }
+}
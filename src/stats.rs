@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use url::Url;
+
+use crate::Favicon;
+
+/// Network statistics of a single fetch.
+/// Failed requests and candidates are counted as well.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchStats {
+    /// Number of bytes of the downloaded HTML pages, including canonical and `www.` variants.
+    pub page_bytes: u64,
+
+    /// Number of bytes of the downloaded favicon candidates.
+    pub icon_bytes: u64,
+
+    /// Number of favicon candidates which were downloaded.
+    pub candidates_tried: usize,
+}
+
+/// Redirects which were followed to fetch a favicon.
+/// Only redirects seen by favilib are recorded. The client built by favilib leaves all redirects to favilib,
+/// custom clients which follow redirects themselves only reveal the requested and the final URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchReport {
+    /// URLs of the page the favicon was found on, from the requested to the final URL.
+    /// Empty if the page was not redirected.
+    pub page_redirects: Vec<Url>,
+
+    /// URLs of the favicon, from the requested to the final URL. Empty if the favicon was not redirected.
+    pub icon_redirects: Vec<Url>,
+}
+
+/// Collects `FetchStats` from the threads fetching the candidates.
+#[derive(Debug, Default)]
+pub(crate) struct StatsRecorder {
+    page_bytes: AtomicU64,
+    icon_bytes: AtomicU64,
+    candidates_tried: AtomicUsize,
+    redirects: Mutex<Vec<Vec<Url>>>,
+    /// Whether decoded candidates which fail `min_dimensions` are kept, for `Favicon::fetch_best_effort`.
+    keeps_undersized: bool,
+    undersized: Mutex<Vec<Favicon>>,
+}
+
+impl StatsRecorder {
+    /// Recorder which also keeps the candidates that are only rejected for being smaller than `min_dimensions`.
+    pub(crate) fn keeping_undersized() -> Self {
+        Self {
+            keeps_undersized: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn record_page(&self, bytes: usize) {
+        self.page_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_candidate(&self) {
+        self.candidates_tried.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_icon(&self, bytes: usize) {
+        self.icon_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records the URLs of a redirected request, starting with the requested URL.
+    pub(crate) fn record_redirects(&self, chain: Vec<Url>) {
+        self.redirects.lock().unwrap().push(chain);
+    }
+
+    /// Redirects of the request to the given URL. Empty if it was not redirected.
+    pub(crate) fn redirects_of(&self, url: &Url) -> Vec<Url> {
+        self.redirects
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|chain| chain.first() == Some(url))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Keeps a candidate which was rejected for being smaller than `min_dimensions`, if the recorder keeps them.
+    pub(crate) fn record_undersized(&self, favicon: &Favicon) {
+        if self.keeps_undersized {
+            self.undersized.lock().unwrap().push(favicon.clone());
+        }
+    }
+
+    /// Largest kept undersized candidate by area. Ties go to the smaller URL, so parallel fetches pick the same one.
+    pub(crate) fn largest_undersized(&self) -> Option<Favicon> {
+        let undersized = self.undersized.lock().unwrap();
+        undersized
+            .iter()
+            .max_by(|a, b| {
+                let area = |favicon: &Favicon| {
+                    let image = favicon.image();
+                    image.width() as u64 * image.height() as u64
+                };
+                area(a)
+                    .cmp(&area(b))
+                    .then_with(|| b.url().as_str().cmp(a.url().as_str()))
+            })
+            .cloned()
+    }
+
+    pub(crate) fn snapshot(&self) -> FetchStats {
+        FetchStats {
+            page_bytes: self.page_bytes.load(Ordering::Relaxed),
+            icon_bytes: self.icon_bytes.load(Ordering::Relaxed),
+            candidates_tried: self.candidates_tried.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use url::Url;
+
+use super::errors::FavilibError;
+use super::http::{HttpClient, HttpResponse};
+use super::Favicon;
+
+/// Serves fixed bodies and answers unknown URLs with an empty 404 response.
+/// Redirects are answered with a 301 response and not followed.
+#[derive(Default)]
+pub(crate) struct MockClient {
+    responses: HashMap<String, Vec<u8>>,
+    redirects: HashMap<String, String>,
+}
+
+impl MockClient {
+    pub(crate) fn with(mut self, url: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.responses.insert(url.to_string(), body.into());
+        self
+    }
+
+    pub(crate) fn with_redirect(mut self, url: &str, location: &str) -> Self {
+        self.redirects.insert(url.to_string(), location.to_string());
+        self
+    }
+}
+
+impl HttpClient for MockClient {
+    fn get(&self, url: &Url, _: &[(&str, &str)]) -> Result<HttpResponse, FavilibError> {
+        if let Some(location) = self.redirects.get(url.as_str()) {
+            return Ok(HttpResponse {
+                url: url.clone(),
+                status: 301,
+                headers: vec![("Location".to_string(), location.clone())],
+                body: vec![],
+            });
+        }
+
+        let (status, body) = match self.responses.get(url.as_str()) {
+            Some(body) => (200, body.clone()),
+            None => (404, vec![]),
+        };
+        Ok(HttpResponse {
+            url: url.clone(),
+            status,
+            headers: vec![],
+            body,
+        })
+    }
+}
+
+/// Path in the temp directory which is unique per process and call, so tests running in parallel don't collide.
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("favilib-{}-{}-{}", std::process::id(), id, name))
+}
+
+pub(crate) fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    image::DynamicImage::new_rgba8(width, height)
+        .write_to(&mut buffer, image::ImageFormat::Png)
+        .unwrap();
+    buffer.into_inner()
+}
+
+/// PNG of an opaque image, which unlike `png_bytes` is no placeholder.
+pub(crate) fn opaque_png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let mut buffer = Cursor::new(Vec::new());
+    solid_image(width, height, Rgba([255, 0, 0, 255]))
+        .write_to(&mut buffer, image::ImageFormat::Png)
+        .unwrap();
+    buffer.into_inner()
+}
+
+/// Image filled with a single color.
+pub(crate) fn solid_image(width: u32, height: u32, color: Rgba<u8>) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, color))
+}
+
+/// Opaque image whose red channel increases from 0 on the left to 255 on the right.
+pub(crate) fn gradient_image(width: u32, height: u32) -> DynamicImage {
+    let max_x = width.saturating_sub(1).max(1);
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, _| {
+        Rgba([(x * 255 / max_x) as u8, 0, 0, 255])
+    }))
+}
+
+/// Opaque image in the given color surrounded by a fully transparent border.
+pub(crate) fn bordered_image(
+    width: u32,
+    height: u32,
+    border: u32,
+    color: Rgba<u8>,
+) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+        let inside = (border..width.saturating_sub(border)).contains(&x)
+            && (border..height.saturating_sub(border)).contains(&y);
+        match inside {
+            true => color,
+            false => Rgba([0, 0, 0, 0]),
+        }
+    }))
+}
+
+/// Builds a favicon from a generated image. The bytes are the raw pixels of the image.
+pub(crate) fn fixture_favicon(image: DynamicImage) -> Favicon {
+    let url = Url::parse("https://example.com/favicon.png").unwrap();
+    Favicon::from_parts(url, image.clone().into_bytes(), image)
+}
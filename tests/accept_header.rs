@@ -0,0 +1,53 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::{Favicon, FetchOptions, ImageFormat, Url};
+
+#[test]
+fn test_fetch_sends_accept_header_for_icons() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" => Response::ok(r#"<head><link rel="icon" href="/icon"></head>"#),
+        "/icon" if request.header("accept") == Some("image/png,*/*") => {
+            Response::ok(png_bytes(16, 16))
+        }
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&base_url).unwrap();
+
+    assert!(Favicon::fetch(url.clone(), None).is_err());
+
+    let options = FetchOptions {
+        icon_accept: Some("image/png,*/*".to_string()),
+        ..Default::default()
+    };
+    let favicon = Favicon::fetch_with_options(url, None, &options).unwrap();
+    assert_eq!(favicon.url().path(), "/icon");
+    assert_eq!(favicon.format_of_bytes(), Some(ImageFormat::Png));
+}
+
+#[test]
+fn test_fetch_sends_accept_language_for_pages_only() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" if request.header("accept-language") == Some("de") => {
+            Response::ok(r#"<head><link rel="icon" href="/de.png"></head>"#)
+        }
+        "/" => Response::ok(r#"<head><link rel="icon" href="/en.png"></head>"#),
+        "/de.png" | "/en.png" if request.header("accept-language").is_none() => {
+            Response::ok(png_bytes(16, 16))
+        }
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&base_url).unwrap();
+
+    let favicon = Favicon::fetch(url.clone(), None).unwrap();
+    assert_eq!(favicon.url().path(), "/en.png");
+
+    let options = FetchOptions {
+        accept_language: Some("de".to_string()),
+        ..Default::default()
+    };
+    let favicon = Favicon::fetch_with_options(url, None, &options).unwrap();
+    assert_eq!(favicon.url().path(), "/de.png");
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::{Favicon, Url};
+
+#[test]
+fn test_fetch_sends_credentials_of_url() {
+    let base_url = serve(|request| {
+        // "user:p@ss" encoded as base64
+        if request.header("authorization") != Some("Basic dXNlcjpwQHNz") {
+            return Response {
+                status: 401,
+                headers: vec![],
+                body: vec![],
+            };
+        }
+
+        match request.path.as_str() {
+            "/" => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+            "/icon.png" => Response::ok(png_bytes(16, 16)),
+            _ => Response::not_found(),
+        }
+    });
+    let mut url = Url::parse(&base_url).unwrap();
+    url.set_username("user").unwrap();
+    url.set_password(Some("p@ss")).unwrap();
+
+    let favicon = Favicon::fetch(url, None).unwrap();
+    assert_eq!(favicon.url().path(), "/icon.png");
+}
@@ -0,0 +1,117 @@
+//! Minimal HTTP server for integration tests.
+
+// Every test binary uses a different subset of the helpers.
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Self {
+            status: 404,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Starts a server on a random local port and returns its base URL.
+/// The server runs until the test process exits.
+pub fn serve<F>(handler: F) -> String
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut request_parts = request_line.split_whitespace();
+            let method = request_parts.next().unwrap_or("GET").to_string();
+            let path = request_parts.next().unwrap_or("/").to_string();
+
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+            }
+
+            let response = handler(&Request {
+                method,
+                path,
+                headers,
+            });
+
+            let mut head = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                response.status,
+                response.body.len()
+            );
+            for (name, value) in &response.headers {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            head.push_str("\r\n");
+
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&response.body);
+        }
+    });
+
+    format!("http://{}", address)
+}
+
+pub fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgba8(width, height)
+        .write_to(&mut buffer, image::ImageFormat::Png)
+        .unwrap();
+    buffer.into_inner()
+}
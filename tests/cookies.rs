@@ -0,0 +1,29 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::{Client, Favicon, Url};
+
+#[test]
+fn test_fetch_keeps_cookies_of_passed_client() {
+    let base_url = serve(|request| {
+        let has_session = request
+            .header("cookie")
+            .is_some_and(|cookie| cookie.contains("session=1"));
+
+        match (request.path.as_str(), has_session) {
+            ("/", false) => Response::ok("<head></head>").with_header("Set-Cookie", "session=1"),
+            ("/", true) => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+            ("/icon.png", true) => Response::ok(png_bytes(16, 16)),
+            _ => Response::not_found(),
+        }
+    });
+    let url = Url::parse(&base_url).unwrap();
+    let client = Client::builder().cookie_store(true).build().unwrap();
+
+    assert!(Favicon::fetch(url.clone(), Some(client.clone())).is_err());
+
+    let favicon = Favicon::fetch(url, Some(client)).unwrap();
+    assert_eq!(favicon.url().path(), "/icon.png");
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::{fetch_to_writer, ImageFormat, ImageSize, Url};
+
+#[test]
+fn test_fetch_to_writer_writes_resized_favicon() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+        "/icon.png" => Response::ok(png_bytes(64, 64)),
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&base_url).unwrap();
+    let mut buffer = Vec::new();
+
+    fetch_to_writer(url, ImageSize::Small, ImageFormat::Png, &mut buffer, None).unwrap();
+
+    let image = image::load_from_memory_with_format(&buffer, ImageFormat::Png).unwrap();
+    assert_eq!((image.width(), image.height()), (16, 16));
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::{Favicon, FetchOptions, Url};
+
+#[test]
+fn test_fetch_sends_host_override() {
+    let base_url = serve(|request| {
+        if request.header("host") != Some("staging.example.com") {
+            return Response::not_found();
+        }
+        match request.path.as_str() {
+            "/" => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+            "/icon.png" => Response::ok(png_bytes(16, 16)),
+            _ => Response::not_found(),
+        }
+    });
+    let url = Url::parse(&base_url).unwrap();
+
+    assert!(Favicon::fetch(url.clone(), None).is_err());
+
+    let options = FetchOptions {
+        host_override: Some("staging.example.com".to_string()),
+        ..Default::default()
+    };
+    let favicon = Favicon::fetch_with_options(url, None, &options).unwrap();
+    assert_eq!(
+        favicon.url().as_str(),
+        "http://staging.example.com/icon.png"
+    );
+    assert_eq!(
+        favicon.page_url().map(Url::as_str),
+        Some("http://staging.example.com/")
+    );
+}
+
+#[test]
+fn test_host_override_is_not_sent_to_other_hosts() {
+    let cdn_url = serve(|request| match request.header("host") {
+        Some("staging.example.com") => Response::not_found(),
+        _ => Response::ok(png_bytes(16, 16)),
+    });
+    let page = format!(
+        r#"<head><link rel="icon" href="{}/icon.png"></head>"#,
+        cdn_url
+    );
+    let base_url = serve(move |_| Response::ok(page.clone()));
+    let options = FetchOptions {
+        host_override: Some("staging.example.com".to_string()),
+        ..Default::default()
+    };
+
+    let favicon =
+        Favicon::fetch_with_options(Url::parse(&base_url).unwrap(), None, &options).unwrap();
+    assert_eq!(favicon.url().as_str(), format!("{}/icon.png", cdn_url));
+}
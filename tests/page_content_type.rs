@@ -0,0 +1,39 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::errors::FavilibError;
+use favilib::{Favicon, FetchOptions, Url};
+
+#[test]
+fn test_fetch_uses_image_url_as_favicon() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/logo.png" => Response::ok(png_bytes(32, 32)).with_header("Content-Type", "image/png"),
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&format!("{}/logo.png", base_url)).unwrap();
+
+    let favicon = Favicon::fetch(url.clone(), None).unwrap();
+
+    assert_eq!(favicon.url(), &url);
+    assert_eq!(favicon.page_url(), Some(&url));
+}
+
+#[test]
+fn test_fetch_rejects_pages_which_are_not_html() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/report.pdf" => Response::ok("%PDF-1.7").with_header("Content-Type", "application/pdf"),
+        "/favicon.ico" => Response::ok(png_bytes(16, 16)),
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&format!("{}/report.pdf", base_url)).unwrap();
+    let options = FetchOptions {
+        disable_www_fallback: true,
+        ..Default::default()
+    };
+
+    let result = Favicon::fetch_with_options(url, None, &options);
+
+    assert!(matches!(result, Err(FavilibError::ContentTypeError(t)) if t == "application/pdf"));
+}
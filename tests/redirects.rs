@@ -0,0 +1,99 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::{Client, Favicon, FetchOptions, Url};
+
+#[test]
+fn test_fetch_with_report_records_icon_redirects() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+        "/icon.png" => Response {
+            status: 302,
+            headers: vec![],
+            body: vec![],
+        }
+        .with_header("Location", "/static/icon.png"),
+        "/static/icon.png" => Response::ok(png_bytes(16, 16)),
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&base_url).unwrap();
+    let client = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    let (favicon, report) = Favicon::fetch_with_report(url, &client, &FetchOptions::default());
+
+    assert_eq!(favicon.unwrap().url().path(), "/icon.png");
+    assert!(report.page_redirects.is_empty());
+    assert_eq!(
+        report
+            .icon_redirects
+            .iter()
+            .map(Url::path)
+            .collect::<Vec<_>>(),
+        vec!["/icon.png", "/static/icon.png"]
+    );
+}
+
+#[test]
+fn test_default_client_leaves_redirects_to_favilib() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" => Response {
+            status: 301,
+            headers: vec![],
+            body: vec![],
+        }
+        .with_header("Location", "/home"),
+        "/home" => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+        "/icon.png" => Response::ok(png_bytes(16, 16)),
+        _ => Response::not_found(),
+    });
+    let url = Url::parse(&base_url).unwrap();
+    let client = favilib::default_client(&FetchOptions::default()).unwrap();
+
+    let (favicon, report) = Favicon::fetch_with_report(url, &client, &FetchOptions::default());
+
+    assert_eq!(favicon.unwrap().url().path(), "/icon.png");
+    assert_eq!(
+        report
+            .page_redirects
+            .iter()
+            .map(Url::path)
+            .collect::<Vec<_>>(),
+        vec!["/", "/home"]
+    );
+}
+
+#[test]
+fn test_redirects_keep_credentials_within_origin() {
+    let base_url = serve(|request| {
+        // "user:pass" encoded as base64
+        if request.header("authorization") != Some("Basic dXNlcjpwYXNz") {
+            return Response {
+                status: 401,
+                headers: vec![],
+                body: vec![],
+            };
+        }
+        match request.path.as_str() {
+            "/" => Response {
+                status: 301,
+                headers: vec![],
+                body: vec![],
+            }
+            .with_header("Location", "/home"),
+            "/home" => Response::ok(r#"<head><link rel="icon" href="/icon.png"></head>"#),
+            "/icon.png" => Response::ok(png_bytes(16, 16)),
+            _ => Response::not_found(),
+        }
+    });
+    let mut url = Url::parse(&base_url).unwrap();
+    url.set_username("user").unwrap();
+    url.set_password(Some("pass")).unwrap();
+
+    let favicon = Favicon::fetch(url, None).unwrap();
+    assert_eq!(favicon.url().path(), "/icon.png");
+}
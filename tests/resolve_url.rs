@@ -0,0 +1,127 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use common::{png_bytes, serve, Response};
+use favilib::errors::FavilibError;
+use favilib::{default_client, Favicon, FetchOptions, Url};
+use std::sync::{Arc, Mutex};
+
+const PAGE: &str = r#"<html><head>
+<link rel="icon" href="/missing.png">
+<link rel="icon" href="/page.html">
+<link rel="icon" href="/icon.png">
+</head></html>"#;
+
+#[test]
+fn test_resolve_url_returns_first_valid_candidate() {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let recorded = requests.clone();
+    let base_url = serve(move |request| {
+        recorded
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", request.method, request.path));
+        match request.path.as_str() {
+            "/" => Response::ok(PAGE).with_header("Content-Type", "text/html"),
+            "/page.html" => Response::ok(PAGE).with_header("Content-Type", "text/html"),
+            "/icon.png" => Response::ok(png_bytes(16, 16)).with_header("Content-Type", "image/png"),
+            _ => Response::not_found(),
+        }
+    });
+    let options = FetchOptions::default();
+    let client = default_client(&options).unwrap();
+
+    let url = Favicon::resolve_url(Url::parse(&base_url).unwrap(), &client, &options).unwrap();
+
+    assert_eq!(url.as_str(), format!("{}/icon.png", base_url));
+    assert_eq!(
+        *requests.lock().unwrap(),
+        [
+            "GET /",
+            "HEAD /missing.png",
+            "HEAD /page.html",
+            "HEAD /icon.png"
+        ]
+    );
+}
+
+#[test]
+fn test_resolve_url_reports_rejected_candidates() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" => Response::ok(PAGE).with_header("Content-Type", "text/html"),
+        "/page.html" => Response::ok(PAGE).with_header("Content-Type", "text/html"),
+        _ => Response::not_found(),
+    });
+    let options = FetchOptions::default();
+    let client = default_client(&options).unwrap();
+
+    let result = Favicon::resolve_url(Url::parse(&base_url).unwrap(), &client, &options);
+
+    let Err(FavilibError::AllCandidatesFailedError { attempts }) = result else {
+        panic!("expected AllCandidatesFailedError, got {:?}", result);
+    };
+    let reasons: Vec<_> = attempts.iter().map(|(_, e)| e.to_string()).collect();
+    assert_eq!(
+        reasons,
+        [
+            "Status 404",
+            "Content type text/html is not a supported image",
+            "Status 404",
+            "Status 404"
+        ]
+    );
+}
+
+#[test]
+fn test_resolve_url_sniffs_candidates_if_head_is_not_allowed() {
+    let ranges = Arc::new(Mutex::new(vec![]));
+    let recorded = ranges.clone();
+    let base_url = serve(
+        move |request| match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/") => Response::ok(PAGE).with_header("Content-Type", "text/html"),
+            ("HEAD", _) => Response {
+                status: 405,
+                headers: vec![],
+                body: vec![],
+            },
+            ("GET", "/icon.png") => {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(request.header("Range").map(String::from));
+                Response::ok(png_bytes(16, 16))
+            }
+            _ => Response::not_found(),
+        },
+    );
+    let options = FetchOptions::default();
+    let client = default_client(&options).unwrap();
+
+    let url = Favicon::resolve_url(Url::parse(&base_url).unwrap(), &client, &options).unwrap();
+
+    assert_eq!(url.as_str(), format!("{}/icon.png", base_url));
+    assert_eq!(*ranges.lock().unwrap(), [Some("bytes=0-1023".to_string())]);
+}
+
+#[test]
+fn test_resolve_url_follows_canonical_links() {
+    let base_url = serve(|request| match request.path.as_str() {
+        "/" => {
+            Response::ok(r#"<html><head><link rel="canonical" href="/canonical"></head></html>"#)
+                .with_header("Content-Type", "text/html")
+        }
+        "/canonical" => Response::ok(PAGE).with_header("Content-Type", "text/html"),
+        "/icon.png" => Response::ok(png_bytes(16, 16)).with_header("Content-Type", "image/png"),
+        _ => Response::not_found(),
+    });
+    let options = FetchOptions {
+        follow_canonical: true,
+        ..Default::default()
+    };
+    let client = default_client(&options).unwrap();
+
+    let url = Favicon::resolve_url(Url::parse(&base_url).unwrap(), &client, &options).unwrap();
+
+    assert_eq!(url.as_str(), format!("{}/icon.png", base_url));
+}